@@ -0,0 +1,320 @@
+pub mod egui_device;
+#[cfg(feature = "gamepad")]
+pub mod gamepad_device;
+pub mod bindings_screen;
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    MoveDown,
+    Jump,
+    Mine,
+    UseItem,
+    HotbarNext,
+    HotbarPrevious,
+    // index into the hotbar, starting at 0
+    HotbarSlot(u8),
+}
+
+impl Action {
+    pub const ALL: [Action; 17] = [
+        Action::MoveLeft, Action::MoveRight, Action::MoveDown, Action::Jump,
+        Action::Mine, Action::UseItem, Action::HotbarNext, Action::HotbarPrevious,
+        Action::HotbarSlot(0), Action::HotbarSlot(1), Action::HotbarSlot(2),
+        Action::HotbarSlot(3), Action::HotbarSlot(4), Action::HotbarSlot(5),
+        Action::HotbarSlot(6), Action::HotbarSlot(7), Action::HotbarSlot(8),
+    ];
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GamepadButton {
+    South,
+    East,
+    North,
+    West,
+    LeftBumper,
+    RightBumper,
+    LeftTrigger,
+    RightTrigger,
+    Select,
+    Start,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GamepadAxis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AxisDirection {
+    Positive,
+    Negative,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Binding {
+    Key(egui::Key),
+    Mouse(egui::PointerButton),
+    ScrollUp,
+    ScrollDown,
+    GamepadButton(GamepadButton),
+    // one direction of a stick, so a single axis can drive two opposite actions
+    GamepadAxis(GamepadAxis, AxisDirection),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeviceKind {
+    KeyboardAndMouse,
+    Gamepad,
+}
+
+impl Binding {
+    pub fn device_kind(&self) -> DeviceKind {
+        match self {
+            Binding::Key(_) | Binding::Mouse(_) | Binding::ScrollUp | Binding::ScrollDown => DeviceKind::KeyboardAndMouse,
+            Binding::GamepadButton(_) | Binding::GamepadAxis(_, _) => DeviceKind::Gamepad,
+        }
+    }
+}
+
+// a source of raw input, the action layer combines every device so actions can be bound to any of them
+pub trait InputDevice {
+    // called once per frame before any values are read
+    fn poll(&mut self, ctx: &egui::Context);
+    // from 0.0 when released to 1.0 when fully held, bindings of other devices return 0.0
+    fn value(&self, binding: &Binding) -> f32;
+    // the binding went from released to held during this frame
+    fn pressed(&self, binding: &Binding) -> bool;
+    // any binding of this device pressed during this frame, used to rebind actions
+    fn pressed_binding(&self) -> Option<Binding>;
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct InputBindings {
+    pub bindings: BTreeMap<Action, Vec<Binding>>,
+}
+
+impl InputBindings {
+    pub const DEFAULT_PATH: &'static str = "input.ron";
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text: String = std::fs::read_to_string(path).map_err(|error| format!("Failed to read {}: {}", path.display(), error))?;
+
+        ron::from_str(&text).map_err(|error| format!("Invalid input bindings in {}: {}", path.display(), error))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let text: String = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()).map_err(|error| error.to_string())?;
+
+        std::fs::write(path, text).map_err(|error| format!("Failed to write {}: {}", path.display(), error))
+    }
+
+    pub fn get(&self, action: Action) -> &[Binding] {
+        self.bindings.get(&action).map_or(&[], |bindings| bindings.as_slice())
+    }
+
+    // replaces the bindings of the action on the same kind of device, so rebinding a key keeps the gamepad binding
+    pub fn rebind(&mut self, action: Action, binding: Binding) {
+        let action_bindings: &mut Vec<Binding> = self.bindings.entry(action).or_default();
+
+        action_bindings.retain(|existing_binding| existing_binding.device_kind() != binding.device_kind());
+        action_bindings.push(binding);
+    }
+}
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        const HOTBAR_KEYS: [egui::Key; 9] = [
+            egui::Key::Num1, egui::Key::Num2, egui::Key::Num3,
+            egui::Key::Num4, egui::Key::Num5, egui::Key::Num6,
+            egui::Key::Num7, egui::Key::Num8, egui::Key::Num9,
+        ];
+
+        let mut bindings: BTreeMap<Action, Vec<Binding>> = BTreeMap::from([
+            (Action::MoveLeft, vec![Binding::Key(egui::Key::A), Binding::GamepadAxis(GamepadAxis::LeftStickX, AxisDirection::Negative), Binding::GamepadButton(GamepadButton::DPadLeft)]),
+            (Action::MoveRight, vec![Binding::Key(egui::Key::D), Binding::GamepadAxis(GamepadAxis::LeftStickX, AxisDirection::Positive), Binding::GamepadButton(GamepadButton::DPadRight)]),
+            (Action::MoveDown, vec![Binding::Key(egui::Key::S), Binding::GamepadAxis(GamepadAxis::LeftStickY, AxisDirection::Negative), Binding::GamepadButton(GamepadButton::DPadDown)]),
+            (Action::Jump, vec![Binding::Key(egui::Key::W), Binding::Key(egui::Key::Space), Binding::GamepadButton(GamepadButton::South)]),
+            (Action::Mine, vec![Binding::Mouse(egui::PointerButton::Primary), Binding::GamepadButton(GamepadButton::RightTrigger)]),
+            (Action::UseItem, vec![Binding::Mouse(egui::PointerButton::Secondary), Binding::GamepadButton(GamepadButton::LeftTrigger)]),
+            (Action::HotbarNext, vec![Binding::ScrollDown, Binding::GamepadButton(GamepadButton::RightBumper)]),
+            (Action::HotbarPrevious, vec![Binding::ScrollUp, Binding::GamepadButton(GamepadButton::LeftBumper)]),
+        ]);
+        for (hotbar_slot, key) in HOTBAR_KEYS.iter().enumerate() {
+            bindings.insert(Action::HotbarSlot(hotbar_slot as u8), vec![Binding::Key(*key)]);
+        }
+
+        Self { bindings }
+    }
+}
+
+// resolves actions from the bindings against every connected input device
+pub struct Input {
+    pub bindings: InputBindings,
+    // cursor in screen coordinates, None while the pointer is outside the window
+    pub pointer_position: Option<egui::Pos2>,
+    // multiplier requested by ctrl + scroll or a pinch gesture this frame, 1.0 when not zooming
+    pub zoom_factor: f32,
+    devices: Vec<Box<dyn InputDevice>>,
+}
+
+impl Input {
+    pub fn new(bindings: InputBindings, devices: Vec<Box<dyn InputDevice>>) -> Self {
+        Self {
+            bindings,
+            pointer_position: None,
+            zoom_factor: 1.0,
+            devices,
+        }
+    }
+
+    pub fn poll(&mut self, ctx: &egui::Context) {
+        self.pointer_position = ctx.input(|i| i.pointer.hover_pos());
+        self.zoom_factor = ctx.input(|i| i.zoom_delta());
+        for device in self.devices.iter_mut() {
+            device.poll(ctx);
+        }
+    }
+
+    // strongest value of any binding of the action on any device
+    pub fn value(&self, action: Action) -> f32 {
+        self.bindings.get(action).iter()
+            .flat_map(|binding| self.devices.iter().map(move |device| device.value(binding)))
+            .fold(0.0, f32::max)
+    }
+
+    pub fn is_down(&self, action: Action) -> bool {
+        self.value(action) > 0.5
+    }
+
+    pub fn pressed(&self, action: Action) -> bool {
+        self.bindings.get(action).iter()
+            .any(|binding| self.devices.iter().any(|device| device.pressed(binding)))
+    }
+
+    pub fn pressed_binding(&self) -> Option<Binding> {
+        self.devices.iter().find_map(|device| device.pressed_binding())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    // the value of every held binding during one poll
+    type Frame = Vec<(Binding, f32)>;
+
+    // plays back one frame per poll, nothing is held once the script runs out
+    #[derive(Default)]
+    struct ScriptedDevice {
+        frames: Vec<Frame>,
+        previous_frame: Frame,
+        current_frame: Frame,
+    }
+
+    impl ScriptedDevice {
+        fn frame_value(frame: &[(Binding, f32)], binding: &Binding) -> f32 {
+            frame.iter().find(|(held_binding, _)| held_binding == binding).map_or(0.0, |&(_, value)| value)
+        }
+    }
+
+    impl InputDevice for ScriptedDevice {
+        fn poll(&mut self, _ctx: &egui::Context) {
+            let next_frame: Frame = if self.frames.is_empty() { Vec::new() } else { self.frames.remove(0) };
+
+            self.previous_frame = std::mem::replace(&mut self.current_frame, next_frame);
+        }
+
+        fn value(&self, binding: &Binding) -> f32 {
+            Self::frame_value(&self.current_frame, binding)
+        }
+
+        fn pressed(&self, binding: &Binding) -> bool {
+            Self::frame_value(&self.current_frame, binding) > 0.5 && Self::frame_value(&self.previous_frame, binding) <= 0.5
+        }
+
+        fn pressed_binding(&self) -> Option<Binding> {
+            self.current_frame.iter()
+                .map(|&(binding, _)| binding)
+                .find(|binding| self.pressed(binding))
+        }
+    }
+
+    fn scripted_input(frames: Vec<Frame>) -> Input {
+        let device: ScriptedDevice = ScriptedDevice { frames, ..ScriptedDevice::default() };
+
+        Input::new(InputBindings::default(), vec![Box::new(device)])
+    }
+
+    const JUMP_KEY: Binding = Binding::Key(egui::Key::Space);
+    const LEFT_STICK_RIGHT: Binding = Binding::GamepadAxis(GamepadAxis::LeftStickX, AxisDirection::Positive);
+
+    #[test]
+    fn pressed_only_on_the_first_held_frame() {
+        let ctx: egui::Context = egui::Context::default();
+        let mut input: Input = scripted_input(vec![vec![], vec![(JUMP_KEY, 1.0)], vec![(JUMP_KEY, 1.0)], vec![]]);
+
+        let mut frames: Vec<(bool, bool)> = Vec::new();
+        for _ in 0..4 {
+            input.poll(&ctx);
+            frames.push((input.is_down(Action::Jump), input.pressed(Action::Jump)));
+        }
+
+        assert_eq!(frames, vec![(false, false), (true, true), (true, false), (false, false)]);
+    }
+
+    #[test]
+    fn value_is_the_strongest_binding_and_is_down_needs_half() {
+        let ctx: egui::Context = egui::Context::default();
+        let mut input: Input = scripted_input(vec![
+            vec![(LEFT_STICK_RIGHT, 0.3)],
+            vec![(LEFT_STICK_RIGHT, 0.8), (Binding::Key(egui::Key::D), 1.0)],
+        ]);
+
+        input.poll(&ctx);
+        assert_eq!(input.value(Action::MoveRight), 0.3);
+        assert!(!input.is_down(Action::MoveRight));
+        assert_eq!(input.value(Action::MoveLeft), 0.0);
+
+        input.poll(&ctx);
+        assert_eq!(input.value(Action::MoveRight), 1.0);
+        assert!(input.is_down(Action::MoveRight));
+    }
+
+    #[test]
+    fn rebinding_replaces_only_the_same_device_kind() {
+        let ctx: egui::Context = egui::Context::default();
+        let rebound_key: Binding = Binding::Key(egui::Key::K);
+        let mut input: Input = scripted_input(vec![
+            vec![(rebound_key, 1.0)],
+            vec![],
+            vec![(JUMP_KEY, 1.0)],
+            vec![],
+            vec![(Binding::GamepadButton(GamepadButton::South), 1.0)],
+        ]);
+
+        input.poll(&ctx);
+        let pressed_binding: Binding = input.pressed_binding().expect("the key was pressed");
+        input.bindings.rebind(Action::Jump, pressed_binding);
+        assert_eq!(input.bindings.get(Action::Jump), &[Binding::GamepadButton(GamepadButton::South), rebound_key]);
+
+        // the old keys no longer jump, the gamepad binding still does
+        input.poll(&ctx);
+        input.poll(&ctx);
+        assert!(!input.pressed(Action::Jump));
+        input.poll(&ctx);
+        input.poll(&ctx);
+        assert!(input.pressed(Action::Jump));
+    }
+}
@@ -0,0 +1,88 @@
+use std::path::Path;
+use crate::input::{Action, Binding, Input, InputBindings};
+
+// settings window listing every action with its bindings, an action is rebound by pressing any key or button
+#[derive(Default)]
+pub struct BindingsScreen {
+    pub is_open: bool,
+    // action waiting for the next pressed binding
+    rebinding_action: Option<Action>,
+    status_message: Option<String>,
+}
+
+impl BindingsScreen {
+    pub fn show(&mut self, ctx: &egui::Context, input: &mut Input) {
+        if let Some(action) = self.rebinding_action {
+            if let Some(binding) = input.pressed_binding() {
+                // escape cancels instead of being bound
+                if binding != Binding::Key(egui::Key::Escape) {
+                    input.bindings.rebind(action, binding);
+                }
+                self.rebinding_action = None;
+            }
+        } else if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            self.is_open = !self.is_open;
+        }
+
+        let mut is_open: bool = self.is_open;
+
+        egui::Window::new("Controls")
+            .open(&mut is_open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("bindings").striped(true).show(ui, |ui| {
+                    for action in Action::ALL {
+                        ui.label(action_name(action));
+
+                        let bindings: Vec<String> = input.bindings.get(action).iter().map(binding_name).collect();
+                        ui.label(bindings.join(", "));
+
+                        let button_text: &str = if self.rebinding_action == Some(action) { "Press a key..." } else { "Rebind" };
+                        if ui.button(button_text).clicked() {
+                            self.rebinding_action = Some(action);
+                        }
+                        ui.end_row();
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    if ui.button("Save").clicked() {
+                        self.status_message = Some(match input.bindings.save(Path::new(InputBindings::DEFAULT_PATH)) {
+                            Ok(()) => format!("Saved to {}", InputBindings::DEFAULT_PATH),
+                            Err(error) => error,
+                        });
+                    }
+                    if ui.button("Reset to defaults").clicked() {
+                        input.bindings = InputBindings::default();
+                    }
+                });
+
+                if let Some(status_message) = &self.status_message {
+                    ui.label(status_message);
+                }
+            });
+
+        self.is_open = is_open;
+        if !self.is_open {
+            self.rebinding_action = None;
+        }
+    }
+}
+
+fn action_name(action: Action) -> String {
+    match action {
+        Action::HotbarSlot(hotbar_slot) => format!("Hotbar slot {}", hotbar_slot + 1),
+        action => format!("{:?}", action),
+    }
+}
+
+fn binding_name(binding: &Binding) -> String {
+    match binding {
+        Binding::Key(key) => key.name().to_string(),
+        Binding::Mouse(button) => format!("Mouse {:?}", button),
+        Binding::ScrollUp => "Scroll up".to_string(),
+        Binding::ScrollDown => "Scroll down".to_string(),
+        Binding::GamepadButton(button) => format!("Gamepad {:?}", button),
+        Binding::GamepadAxis(axis, direction) => format!("Gamepad {:?} {:?}", axis, direction),
+    }
+}
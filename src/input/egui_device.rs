@@ -0,0 +1,45 @@
+use crate::input::{Binding, InputDevice};
+
+// keyboard, mouse buttons and scroll wheel as reported by egui
+#[derive(Default)]
+pub struct EguiDevice {
+    input_state: egui::InputState,
+}
+
+impl InputDevice for EguiDevice {
+    fn poll(&mut self, ctx: &egui::Context) {
+        self.input_state = ctx.input(|i| i.clone());
+    }
+
+    fn value(&self, binding: &Binding) -> f32 {
+        let is_down: bool = match binding {
+            Binding::Key(key) => self.input_state.key_down(*key),
+            Binding::Mouse(button) => self.input_state.pointer.button_down(*button),
+            // scrolling with ctrl held zooms instead
+            Binding::ScrollUp => !self.input_state.modifiers.command && self.input_state.raw_scroll_delta.y > 0.0,
+            Binding::ScrollDown => !self.input_state.modifiers.command && self.input_state.raw_scroll_delta.y < 0.0,
+            Binding::GamepadButton(_) | Binding::GamepadAxis(_, _) => false,
+        };
+
+        if is_down { 1.0 } else { 0.0 }
+    }
+
+    fn pressed(&self, binding: &Binding) -> bool {
+        match binding {
+            Binding::Key(key) => self.input_state.key_pressed(*key),
+            Binding::Mouse(button) => self.input_state.pointer.button_pressed(*button),
+            Binding::ScrollUp | Binding::ScrollDown => self.value(binding) > 0.0,
+            Binding::GamepadButton(_) | Binding::GamepadAxis(_, _) => false,
+        }
+    }
+
+    fn pressed_binding(&self) -> Option<Binding> {
+        self.input_state.events.iter().find_map(|event| match event {
+            egui::Event::Key { key, pressed: true, repeat: false, .. } => Some(Binding::Key(*key)),
+            egui::Event::PointerButton { button, pressed: true, .. } => Some(Binding::Mouse(*button)),
+            egui::Event::MouseWheel { delta, .. } if delta.y > 0.0 => Some(Binding::ScrollUp),
+            egui::Event::MouseWheel { delta, .. } if delta.y < 0.0 => Some(Binding::ScrollDown),
+            _ => None,
+        })
+    }
+}
@@ -0,0 +1,143 @@
+use std::collections::HashSet;
+use crate::input::{AxisDirection, Binding, GamepadAxis, GamepadButton, InputDevice};
+
+// every connected gamepad combined into one device, read through gilrs
+pub struct GamepadDevice {
+    gilrs: gilrs::Gilrs,
+    // buttons held during the previous and the current frame, used to detect presses
+    previous_buttons_down: HashSet<GamepadButton>,
+    buttons_down: HashSet<GamepadButton>,
+    // values of every stick direction in Self::AXES during the previous and the current frame
+    previous_axis_values: [f32; GamepadDevice::AXES.len()],
+    axis_values: [f32; GamepadDevice::AXES.len()],
+}
+
+impl GamepadDevice {
+    // stick values below this are treated as 0.0 so worn sticks do not drift
+    const DEAD_ZONE: f32 = 0.2;
+
+    const BUTTONS: [GamepadButton; 14] = [
+        GamepadButton::South, GamepadButton::East, GamepadButton::North, GamepadButton::West,
+        GamepadButton::LeftBumper, GamepadButton::RightBumper, GamepadButton::LeftTrigger, GamepadButton::RightTrigger,
+        GamepadButton::Select, GamepadButton::Start,
+        GamepadButton::DPadUp, GamepadButton::DPadDown, GamepadButton::DPadLeft, GamepadButton::DPadRight,
+    ];
+
+    const AXES: [(GamepadAxis, AxisDirection); 8] = [
+        (GamepadAxis::LeftStickX, AxisDirection::Negative), (GamepadAxis::LeftStickX, AxisDirection::Positive),
+        (GamepadAxis::LeftStickY, AxisDirection::Negative), (GamepadAxis::LeftStickY, AxisDirection::Positive),
+        (GamepadAxis::RightStickX, AxisDirection::Negative), (GamepadAxis::RightStickX, AxisDirection::Positive),
+        (GamepadAxis::RightStickY, AxisDirection::Negative), (GamepadAxis::RightStickY, AxisDirection::Positive),
+    ];
+
+    // a stick direction counts as pressed when it crosses this value, like a button going down
+    const AXIS_PRESS_THRESHOLD: f32 = 0.5;
+
+    pub fn new() -> Result<Self, String> {
+        Ok(Self {
+            gilrs: gilrs::Gilrs::new().map_err(|error| error.to_string())?,
+            previous_buttons_down: HashSet::new(),
+            buttons_down: HashSet::new(),
+            previous_axis_values: [0.0; Self::AXES.len()],
+            axis_values: [0.0; Self::AXES.len()],
+        })
+    }
+
+    fn axis_index(axis: GamepadAxis, direction: AxisDirection) -> usize {
+        Self::AXES.iter()
+            .position(|&(known_axis, known_direction)| known_axis == axis && known_direction == direction)
+            .expect("every stick direction is in AXES")
+    }
+
+    fn axis_pressed(&self, axis_index: usize) -> bool {
+        self.axis_values[axis_index] > Self::AXIS_PRESS_THRESHOLD && self.previous_axis_values[axis_index] <= Self::AXIS_PRESS_THRESHOLD
+    }
+
+    fn to_gilrs_button(button: GamepadButton) -> gilrs::Button {
+        match button {
+            GamepadButton::South => gilrs::Button::South,
+            GamepadButton::East => gilrs::Button::East,
+            GamepadButton::North => gilrs::Button::North,
+            GamepadButton::West => gilrs::Button::West,
+            GamepadButton::LeftBumper => gilrs::Button::LeftTrigger,
+            GamepadButton::RightBumper => gilrs::Button::RightTrigger,
+            GamepadButton::LeftTrigger => gilrs::Button::LeftTrigger2,
+            GamepadButton::RightTrigger => gilrs::Button::RightTrigger2,
+            GamepadButton::Select => gilrs::Button::Select,
+            GamepadButton::Start => gilrs::Button::Start,
+            GamepadButton::DPadUp => gilrs::Button::DPadUp,
+            GamepadButton::DPadDown => gilrs::Button::DPadDown,
+            GamepadButton::DPadLeft => gilrs::Button::DPadLeft,
+            GamepadButton::DPadRight => gilrs::Button::DPadRight,
+        }
+    }
+
+    fn to_gilrs_axis(axis: GamepadAxis) -> gilrs::Axis {
+        match axis {
+            GamepadAxis::LeftStickX => gilrs::Axis::LeftStickX,
+            GamepadAxis::LeftStickY => gilrs::Axis::LeftStickY,
+            GamepadAxis::RightStickX => gilrs::Axis::RightStickX,
+            GamepadAxis::RightStickY => gilrs::Axis::RightStickY,
+        }
+    }
+
+    fn axis_value(&self, axis: GamepadAxis, direction: AxisDirection) -> f32 {
+        self.gilrs.gamepads()
+            .map(|(_, gamepad)| {
+                let value: f32 = match direction {
+                    AxisDirection::Positive => gamepad.value(Self::to_gilrs_axis(axis)),
+                    AxisDirection::Negative => -gamepad.value(Self::to_gilrs_axis(axis)),
+                };
+
+                if value > Self::DEAD_ZONE { value.min(1.0) } else { 0.0 }
+            })
+            .fold(0.0, f32::max)
+    }
+}
+
+impl InputDevice for GamepadDevice {
+    fn poll(&mut self, _ctx: &egui::Context) {
+        // gamepad state is only updated while events are drained
+        while self.gilrs.next_event().is_some() {}
+
+        self.previous_buttons_down = std::mem::take(&mut self.buttons_down);
+        for button in Self::BUTTONS {
+            if self.gilrs.gamepads().any(|(_, gamepad)| gamepad.is_pressed(Self::to_gilrs_button(button))) {
+                self.buttons_down.insert(button);
+            }
+        }
+
+        self.previous_axis_values = self.axis_values;
+        for (axis_index, &(axis, direction)) in Self::AXES.iter().enumerate() {
+            self.axis_values[axis_index] = self.axis_value(axis, direction);
+        }
+    }
+
+    fn value(&self, binding: &Binding) -> f32 {
+        match binding {
+            Binding::GamepadButton(button) => if self.buttons_down.contains(button) { 1.0 } else { 0.0 },
+            Binding::GamepadAxis(axis, direction) => self.axis_values[Self::axis_index(*axis, *direction)],
+            _ => 0.0,
+        }
+    }
+
+    fn pressed(&self, binding: &Binding) -> bool {
+        match binding {
+            Binding::GamepadButton(button) => self.buttons_down.contains(button) && !self.previous_buttons_down.contains(button),
+            Binding::GamepadAxis(axis, direction) => self.axis_pressed(Self::axis_index(*axis, *direction)),
+            _ => false,
+        }
+    }
+
+    fn pressed_binding(&self) -> Option<Binding> {
+        let pressed_button: Option<Binding> = Self::BUTTONS.iter()
+            .find(|button| self.buttons_down.contains(button) && !self.previous_buttons_down.contains(button))
+            .map(|button| Binding::GamepadButton(*button));
+
+        pressed_button.or_else(|| {
+            (0..Self::AXES.len())
+                .find(|&axis_index| self.axis_pressed(axis_index))
+                .map(|axis_index| Binding::GamepadAxis(Self::AXES[axis_index].0, Self::AXES[axis_index].1))
+        })
+    }
+}
@@ -1,68 +1,373 @@
+mod input;
 mod renderer;
 mod player;
 mod world;
 
 use eframe::{egui, CreationContext, NativeOptions};
+use input::{Input, InputBindings, InputDevice};
+use input::bindings_screen::BindingsScreen;
+use input::egui_device::EguiDevice;
 use renderer::Renderer;
+use renderer::block_atlas::BlockAtlas;
+use renderer::camera::Camera;
+use renderer::frame_stats::FrameStats;
 use player::Player;
+use player::physics_profile::PhysicsProfile;
+use std::sync::Arc;
 use world::World;
+use world::block_registry::BlockRegistry;
+use world::world_generator::{GenerationProgress, WorldGenConfig, WorldGenerationTask};
 
 fn main() -> eframe::Result {
-    let content: Box<Content> = Box::<Content>::default();
+    let world_gen_config: Option<WorldGenConfig> = match parse_world_gen_args(std::env::args().skip(1)) {
+        Ok(world_gen_config) => world_gen_config,
+        Err(error) => {
+            println!("{}", error);
+            println!("Usage: game1 [--seed <number or text>] [--width <blocks>] [--height <blocks>]");
+            std::process::exit(1);
+        },
+    };
+
+    let blocks: Arc<BlockRegistry> = match BlockRegistry::load(std::path::Path::new(BlockRegistry::DEFAULT_PATH)) {
+        Ok(blocks) => Arc::new(blocks),
+        Err(error) => {
+            println!("{}", error);
+            std::process::exit(1);
+        },
+    };
 
     let options: NativeOptions = eframe::NativeOptions::default();
     eframe::run_native(
         "Terrarius",
         options,
-        Box::new(|_cc: &CreationContext| Ok(content)),
+        Box::new(|cc: &CreationContext| Ok(Box::new(Content::new(&cc.egui_ctx, blocks, world_gen_config)))),
     )
 }
 
+// rate of the fixed simulation step, independent of the display refresh rate
+pub const TICKS_PER_SECOND: u32 = 60;
+
 struct Content {
     player: Player,
     world: World,
+    block_atlas: BlockAtlas,
+    camera: Camera,
+    renderer: Renderer,
+    frame_stats: FrameStats,
+    input: Input,
+    bindings_screen: BindingsScreen,
+    physics_profile: PhysicsProfile,
+    // modification time of the physics profile file when it was last loaded, a newer file is reloaded
+    physics_profile_modified: Option<std::time::SystemTime>,
+    // the world being generated in the background, the game is paused behind a loading screen until it is done
+    world_generation: Option<WorldGenerationTask>,
+    // false if an existing save could not be loaded or backed up, so it must not be overwritten on exit
+    autosave: bool,
     last_frame_start: std::time::Instant,
+    // simulation time not yet consumed by ticks, in seconds
+    tick_accumulator: f32,
 }
 
-impl Default for Content {
-    fn default() -> Self {
-        Self {
-            player: Player::default(),
-            world: World::default(),
+impl Content {
+    const SAVE_PATH: &'static str = "world.trw";
+    // frames longer than this are cut short so a hitch does not trigger a long burst of ticks
+    const MAX_FRAME_TIME: f32 = 0.25;
+
+    // a world generation config replaces the saved world with a newly generated one
+    fn new(ctx: &egui::Context, blocks: Arc<BlockRegistry>, world_gen_config: Option<WorldGenConfig>) -> Self {
+        let block_atlas: BlockAtlas = BlockAtlas::new(ctx, &blocks, std::path::Path::new(BlockAtlas::TEXTURE_DIRECTORY));
+
+        let mut autosave: bool = true;
+        let (world, player, world_generation) = match world_gen_config {
+            Some(world_gen_config) => {
+                // the saved world is kept, the new world is saved in its place
+                if std::path::Path::new(Self::SAVE_PATH).exists() {
+                    autosave = Self::move_save_out_of_the_way();
+                }
+
+                Self::start_world_generation(world_gen_config, blocks)
+            },
+            None => match World::load(std::path::Path::new(Self::SAVE_PATH), blocks.clone()) {
+                Ok((world, player)) => (world, player, None),
+                Err(error) => {
+                    // a save that can not be loaded is moved out of the way so the new world does not overwrite it
+                    if error.kind() != std::io::ErrorKind::NotFound {
+                        println!("Failed to load {}: {}", Self::SAVE_PATH, error);
+                        autosave = Self::move_save_out_of_the_way();
+                    }
+
+                    Self::start_world_generation(WorldGenConfig::default(), blocks)
+                },
+            },
+        };
+
+        let bindings: InputBindings = match InputBindings::load(std::path::Path::new(InputBindings::DEFAULT_PATH)) {
+            Ok(bindings) => bindings,
+            Err(error) => {
+                if std::path::Path::new(InputBindings::DEFAULT_PATH).exists() {
+                    println!("{}", error);
+                }
+
+                InputBindings::default()
+            },
+        };
+
+        #[allow(unused_mut)]
+        let mut devices: Vec<Box<dyn InputDevice>> = vec![Box::new(EguiDevice::default())];
+        #[cfg(feature = "gamepad")]
+        match input::gamepad_device::GamepadDevice::new() {
+            Ok(gamepad_device) => devices.push(Box::new(gamepad_device)),
+            Err(error) => println!("Gamepad support unavailable: {}", error),
+        }
+
+        let (player_center_x, player_center_y) = (player.position_x + Player::WIDTH / 2.0, player.position_y + Player::HEIGHT / 2.0);
+
+        let mut content: Content = Self {
+            player,
+            world,
+            block_atlas,
+            camera: Camera::new(player_center_x, player_center_y),
+            renderer: Renderer::default(),
+            frame_stats: FrameStats::default(),
+            input: Input::new(bindings, devices),
+            bindings_screen: BindingsScreen::default(),
+            physics_profile: PhysicsProfile::default(),
+            physics_profile_modified: Self::physics_profile_modified(),
+            world_generation,
+            autosave,
             last_frame_start: std::time::Instant::now(),
+            tick_accumulator: 0.0,
+        };
+        content.reload_physics_profile();
+
+        content
+    }
+
+    // keeps the current profile if the file is missing or invalid so a typo while tuning does not stop the game
+    fn reload_physics_profile(&mut self) {
+        match PhysicsProfile::load(std::path::Path::new(PhysicsProfile::DEFAULT_PATH)) {
+            Ok(physics_profile) => self.physics_profile = physics_profile,
+            Err(error) => println!("{}", error),
         }
     }
+
+    fn reload_physics_profile_if_changed(&mut self) {
+        let modified: Option<std::time::SystemTime> = Self::physics_profile_modified();
+
+        if modified != self.physics_profile_modified {
+            self.physics_profile_modified = modified;
+            self.reload_physics_profile();
+        }
+    }
+
+    fn physics_profile_modified() -> Option<std::time::SystemTime> {
+        std::fs::metadata(PhysicsProfile::DEFAULT_PATH).and_then(|metadata| metadata.modified()).ok()
+    }
+
+    // backs up the save before a new world replaces it, returns false if that failed and the new world must not be saved
+    fn move_save_out_of_the_way() -> bool {
+        match Self::back_up_save() {
+            Ok(backup_path) => {
+                println!("Moved {} to {}", Self::SAVE_PATH, backup_path.display());
+                true
+            },
+            Err(error) => {
+                println!("Failed to back up {}, the world will not be saved: {}", Self::SAVE_PATH, error);
+                false
+            },
+        }
+    }
+
+    // renames the save to the first free backup path and returns that path
+    fn back_up_save() -> std::io::Result<std::path::PathBuf> {
+        let backup_path: std::path::PathBuf = (0..)
+            .map(|index: u32| match index {
+                0 => std::path::PathBuf::from(format!("{}.bak", Self::SAVE_PATH)),
+                _ => std::path::PathBuf::from(format!("{}.bak{}", Self::SAVE_PATH, index)),
+            })
+            .find(|path| !path.exists())
+            .expect("there is always a free backup path");
+
+        std::fs::rename(Self::SAVE_PATH, &backup_path)?;
+
+        Ok(backup_path)
+    }
+
+    // the returned world is empty and only stands in until the generated one replaces it
+    fn start_world_generation(world_gen_config: WorldGenConfig, blocks: Arc<BlockRegistry>) -> (World, Player, Option<WorldGenerationTask>) {
+        let world: World = World::new(world_gen_config.map_width, world_gen_config.map_height, world_gen_config.seed, blocks.clone());
+        let player: Player = Player::new(&world);
+
+        (world, player, Some(WorldGenerationTask::start(world_gen_config, blocks)))
+    }
+
+    // shows the progress of the world generation and switches to the world once it is done
+    fn update_world_generation(&mut self, ctx: &egui::Context) {
+        let Some(world_generation) = &mut self.world_generation else {
+            return;
+        };
+
+        match world_generation.poll() {
+            Ok(Some(world)) => {
+                self.player = Player::new(&world);
+                self.world = world;
+                self.camera = Camera::new(self.player.position_x + Player::WIDTH / 2.0, self.player.position_y + Player::HEIGHT / 2.0);
+                self.renderer = Renderer::default();
+                self.world_generation = None;
+                // the time spent generating is not simulated
+                self.last_frame_start = std::time::Instant::now();
+                self.tick_accumulator = 0.0;
+            },
+            Ok(None) => {
+                let progress: &GenerationProgress = &world_generation.progress;
+
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    ui.vertical_centered(|ui| {
+                        ui.add_space(ui.available_height() / 3.0);
+                        ui.heading("Generating world");
+                        ui.add(
+                            egui::ProgressBar::new(progress.fraction())
+                                .desired_width(400.0)
+                                .text(format!("{} ({}/{})", progress.pass_name, progress.pass_index + 1, progress.pass_count)),
+                        );
+                    });
+                });
+            },
+            Err(error) => {
+                println!("{}", error);
+                std::process::exit(1);
+            },
+        }
+
+        ctx.request_repaint();
+    }
+}
+
+// returns None if no world generation options were given
+fn parse_world_gen_args(mut args: impl Iterator<Item = String>) -> Result<Option<WorldGenConfig>, String> {
+    let mut world_gen_config: Option<WorldGenConfig> = None;
+
+    while let Some(flag) = args.next() {
+        let value: String = args.next().ok_or(format!("Missing value for {}", flag))?;
+        let config: &mut WorldGenConfig = world_gen_config.get_or_insert_with(WorldGenConfig::default);
+
+        match flag.as_str() {
+            "--seed" => config.seed = WorldGenConfig::seed_from_str(&value),
+            "--width" => config.map_width = value.parse().map_err(|_| format!("Invalid width {}", value))?,
+            "--height" => config.map_height = value.parse().map_err(|_| format!("Invalid height {}", value))?,
+            _ => return Err(format!("Unknown option {}", flag)),
+        }
+    }
+
+    if let Some(config) = &world_gen_config {
+        config.validate()?;
+    }
+
+    Ok(world_gen_config)
 }
 
 impl eframe::App for Content {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        if self.world_generation.is_some() {
+            self.update_world_generation(ctx);
+            return;
+        }
+
         egui::CentralPanel::default().show(ctx, |ui| {
 
-            let delta_time: u32 = self.last_frame_start.elapsed().subsec_millis();
+            let frame_time: f32 = self.last_frame_start.elapsed().as_secs_f32().min(Self::MAX_FRAME_TIME);
             self.last_frame_start = std::time::Instant::now();
 
             let window_size: egui::Rect = ctx.input(|i: &egui::InputState| i.screen_rect());
-            let window_width: f32 = window_size.max.x;
-            let window_height: f32 = window_size.max.y;
 
             // update stage
-            self.player.update(&mut self.world, ctx, window_width, window_height, delta_time);
+            self.reload_physics_profile_if_changed();
+
+            // input is applied before the ticks so they use this frame's input, the targeted block is picked with
+            // last frame's camera since that is what the pointer was over
+            self.input.poll(ctx);
+            self.bindings_screen.show(ctx, &mut self.input);
+
+            // the player ignores input while the controls are being changed
+            if self.bindings_screen.is_open {
+                self.player.movement_input = 0.0;
+                self.player.jump_held = false;
+                self.player.mining = None;
+            } else {
+                self.player.update(&mut self.world, &self.input, &self.camera, frame_time);
+            }
+
+            let tick_duration: f32 = 1.0 / TICKS_PER_SECOND as f32;
+
+            self.tick_accumulator += frame_time;
+            while self.tick_accumulator >= tick_duration {
+                self.player.tick(&self.world, &self.physics_profile);
+                self.tick_accumulator -= tick_duration;
+            }
+
+            // how far the current frame is between the last tick and the next one
+            let interpolation: f32 = self.tick_accumulator / tick_duration;
+
+            let (player_render_x, player_render_y) = self.player.interpolated_position(interpolation);
+            self.camera.update(
+                &self.world,
+                window_size,
+                player_render_x + Player::WIDTH / 2.0,
+                player_render_y + Player::HEIGHT / 2.0,
+                self.input.zoom_factor,
+                frame_time,
+            );
+
+            // only the chunks around the view stay loaded
+            let (min_visible_block_x, _, max_visible_block_x, _) = self.camera.visible_blocks();
+            self.world.update_loaded_chunks(min_visible_block_x, max_visible_block_x);
 
             // render stage
-            let mut mesh: egui::Mesh = egui::Mesh::default();
+            let render_start: std::time::Instant = std::time::Instant::now();
 
-            self.world.update_frame_mesh(&self.player, &mut mesh, window_width, window_height);
-            self.player.update_frame_mesh(&mut mesh, window_width, window_height);
+            self.renderer.invalidate_chunks(&self.world.take_changed_chunks());
+            self.player.draw(&mut self.renderer, &self.block_atlas, interpolation);
+            self.renderer.render(ui.painter(), &self.camera, &self.world, &self.block_atlas);
 
-            ui.painter().add(egui::Shape::Mesh(mesh));
+            self.frame_stats.record(frame_time, render_start.elapsed().as_secs_f32());
 
             // temp statistics
             let player_block_x: i32 = (self.player.position_x / Renderer::BLOCK_PIXEL_COUNT).trunc() as i32;
             let player_block_y: i32 = (self.player.position_y / Renderer::BLOCK_PIXEL_COUNT).trunc() as i32;
-            ui.label(format!("X: {}, Y: {}, Delta time: {:02}", self.player.position_x, self.player.position_y, delta_time));
-            ui.label(format!("X: {}, Y: {}, Block: {}", player_block_x, player_block_y, self.world.get_block(player_block_x as u32, player_block_y as u32)));
+            ui.label(format!("X: {}, Y: {}", self.player.position_x, self.player.position_y));
+            ui.label(format!(
+                "Frame time: {:.2} ms avg, {:.2} ms max, render {:.2} ms avg",
+                self.frame_stats.average_frame_time() * 1000.0,
+                self.frame_stats.max_frame_time() * 1000.0,
+                self.frame_stats.average_render_time() * 1000.0,
+            ));
+            let block_name: &str = match self.world.get_block(player_block_x, player_block_y).and_then(|block_type| self.world.blocks.get(block_type)) {
+                Some(block_definition) => &block_definition.name,
+                None => "none",
+            };
+            ui.label(format!("X: {}, Y: {}, Block: {}", player_block_x, player_block_y, block_name));
+            ui.label(format!(
+                "Loaded chunks: {}, unloaded chunks: {}, cached chunk meshes: {}, rebuilt this frame: {}",
+                self.world.loaded_chunk_count(),
+                self.world.unloaded_chunk_count(),
+                self.renderer.cached_chunk_mesh_count(),
+                self.renderer.rebuilt_chunk_mesh_count,
+            ));
+
+            self.player.inventory.show_hotbar(ctx, &self.world.blocks, &self.block_atlas);
 
             ctx.request_repaint();
         });
     }
+
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        // the stand in world would overwrite the save
+        if self.world_generation.is_some() || !self.autosave {
+            return;
+        }
+
+        if let Err(error) = self.world.save(std::path::Path::new(Self::SAVE_PATH), &self.player) {
+            println!("Failed to save {}: {}", Self::SAVE_PATH, error);
+        }
+    }
 }
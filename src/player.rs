@@ -1,183 +1,347 @@
+pub mod inventory;
+pub mod physics_profile;
+
 use eframe::egui;
-use crate::world::block_textures::BlockTypes;
-use crate::world::block_data::SOLIDS;
-use crate::renderer::Renderer;
+use crate::world::block_registry::{BlockFlag, AIR};
+use crate::renderer::{DrawCommand, RenderLayer, Renderer};
+use crate::renderer::block_atlas::BlockAtlas;
+use crate::renderer::camera::Camera;
+use crate::input::{Action, Input};
 use crate::World;
+use crate::world::collision::{self, Aabb, CollisionResult, Contacts};
+use inventory::Inventory;
+use physics_profile::PhysicsProfile;
+
+pub struct MiningProgress {
+    pub block_x: i32,
+    pub block_y: i32,
+    // the wall behind the block is mined when there is no block in front of it
+    pub is_wall: bool,
+    // from 0.0 to 1.0, the block breaks at 1.0
+    pub progress: f32,
+}
+
+pub struct TargetedBlock {
+    pub block_x: i32,
+    pub block_y: i32,
+    // within reach distance and not hidden behind solid blocks
+    pub is_reachable: bool,
+}
 
 pub struct Player {
     pub position_x: f32,
     pub velocity_x: f32,
     pub position_y: f32,
     pub velocity_y: f32,
+    // position at the start of the current tick, used to interpolate between ticks when rendering
+    pub previous_position_x: f32,
+    pub previous_position_y: f32,
+    // -1.0 to 1.0, horizontal movement read from the keyboard every frame and applied every tick
+    pub movement_input: f32,
+    pub jump_requested: bool,
+    // releasing jump while still rising cuts the jump short
+    pub jump_held: bool,
+    // contacts with solid blocks after the last tick
+    pub contacts: Contacts,
+    // vertical distance the rendered player lags behind after stepping onto a ledge, shrinks every tick
+    pub step_offset: f32,
+    pub previous_step_offset: f32,
+    pub inventory: Inventory,
+    pub mining: Option<MiningProgress>,
+    // block under the cursor, updated every frame
+    pub targeted_block: Option<TargetedBlock>,
+    // maximum distance in blocks from the player's center to the center of a block it can interact with
+    pub reach_distance: f32,
 }
 
 impl Player {
-    pub const MAX_X_VELOCITY: f32 = 0.2;
-    pub const MAX_Y_VELOCITY: f32 = 2.0;
-    //pub const MAX_X_VELOCITY: f32 = 10.0;
-    //pub const MAX_Y_VELOCITY: f32 = 10.0;
-
-    pub const MOVEMENT_ACCELERATION: f32 = 0.01;
-    //pub const MOVEMENT_ACCELERATION: f32 = 0.1;
-    pub const JUMP_VELOCITY: f32 = 0.45;
-    //pub const JUMP_VELOCITY: f32 = 1.0;
-
     pub const WIDTH: f32 = 40.0;
     pub const HEIGHT: f32 = 86.0;
 
-    pub fn update_frame_mesh(&self, mesh: &mut egui::Mesh, window_width: f32, window_height: f32) {
-        mesh.add_colored_rect(
-            egui::Rect::from_x_y_ranges((window_width / 2.0).ceil()..=(window_width / 2.0 + Player::WIDTH).ceil(), (window_height / 2.0).ceil()..=(window_height / 2.0 + Player::HEIGHT).ceil()),
-            match egui::Color32::from_hex("#ff00ff") {
-                Ok(color) => color,
-                Err(_) => {
-                    println!("Invalid HEX string");
-                    egui::Color32::BLACK
-                },
-            },
-        );
-    }
+    // ledges up to this height are stepped onto without jumping
+    pub const MAX_STEP_HEIGHT: f32 = Renderer::BLOCK_PIXEL_COUNT;
+    // pixels per tick the rendered position catches up with the physical position after stepping up
+    const STEP_SMOOTHING_SPEED: f32 = 4.0;
 
-    pub fn update(&mut self, world: &mut World, ctx: &egui::Context, window_width: f32, window_height: f32, delta_time: u32) -> () {
-        if ctx.input(|i| i.pointer.primary_clicked()) {
-            let click_position: Option<egui::Pos2> = ctx.input(|i| i.pointer.interact_pos());
-            match click_position {
-                Some(click_position) => {
-                    let (block_x, block_y) = (((self.position_x + click_position.x - window_width / 2.0) / Renderer::BLOCK_PIXEL_COUNT).floor() as i32, ((self.position_y + click_position.y - window_height / 2.0) / Renderer::BLOCK_PIXEL_COUNT).floor() as i32);
-                    world.set_block(block_x as u32, block_y as u32, BlockTypes::Air as u16);
-                }
-                None => {}
+    pub const DEFAULT_REACH_DISTANCE: f32 = 6.0;
+    const TARGET_OUTLINE_WIDTH: f32 = 2.0;
+
+    pub fn draw(&self, renderer: &mut Renderer, block_atlas: &BlockAtlas, interpolation: f32) {
+        let (render_position_x, render_position_y) = self.interpolated_position(interpolation);
+
+        renderer.submit(RenderLayer::Entities, DrawCommand::Rect {
+            rect: egui::Rect::from_min_size(egui::pos2(render_position_x, render_position_y), egui::vec2(Player::WIDTH, Player::HEIGHT)),
+            color: egui::Color32::from_rgb(255, 0, 255),
+        });
+
+        if let Some(mining) = &self.mining {
+            if let Some(crack_uv) = block_atlas.get_crack_uv(mining.progress) {
+                renderer.submit(RenderLayer::Blocks, DrawCommand::AtlasRect {
+                    rect: Renderer::block_rect(mining.block_x, mining.block_y),
+                    uv: crack_uv,
+                });
             }
         }
-        if ctx.input(|i| i.pointer.secondary_clicked()) {
-            let click_position: Option<egui::Pos2> = ctx.input(|i| i.pointer.interact_pos());
-            match click_position {
-                Some(click_position) => {
-                    let (block_x, block_y) = (((self.position_x + click_position.x - window_width / 2.0) / Renderer::BLOCK_PIXEL_COUNT).floor() as i32, ((self.position_y + click_position.y - window_height / 2.0) / Renderer::BLOCK_PIXEL_COUNT).floor() as i32);
-                    world.set_block(block_x as u32, block_y as u32, BlockTypes::Stone as u16);
-                }
-                None => {}
-            }
+
+        if let Some(targeted_block) = &self.targeted_block {
+            let outline_color: egui::Color32 = if targeted_block.is_reachable {
+                egui::Color32::WHITE
+            } else {
+                egui::Color32::from_rgb(200, 40, 40)
+            };
+
+            renderer.submit(RenderLayer::Overlay, DrawCommand::Outline {
+                rect: Renderer::block_rect(targeted_block.block_x, targeted_block.block_y),
+                width: Player::TARGET_OUTLINE_WIDTH,
+                color: outline_color,
+            });
         }
+    }
 
-        if ctx.input(|i| i.key_pressed(egui::Key::W)) {
-            let player_bottom_edge: f32 = (self.position_y + Player::HEIGHT) / Renderer::BLOCK_PIXEL_COUNT;
-            let block_top_edge: f32 = player_bottom_edge.ceil();
+    pub fn update(&mut self, world: &mut World, input: &Input, camera: &Camera, frame_time: f32) {
+        self.targeted_block = input.pointer_position.map(|cursor_position| {
+            let (block_x, block_y) = camera.screen_to_block(cursor_position);
 
-            if block_top_edge == player_bottom_edge {
-                let left_edge: f32 = (self.position_x / Renderer::BLOCK_PIXEL_COUNT).trunc();
-                let right_edge: f32 = ((self.position_x + Player::WIDTH) / Renderer::BLOCK_PIXEL_COUNT).ceil() - 1.0;
+            TargetedBlock {
+                block_x,
+                block_y,
+                is_reachable: self.can_reach(world, block_x, block_y),
+            }
+        });
+
+        let reachable_target: Option<(i32, i32)> = self.targeted_block.as_ref()
+            .filter(|targeted_block| targeted_block.is_reachable)
+            .map(|targeted_block| (targeted_block.block_x, targeted_block.block_y));
+
+        let mining_target: Option<(i32, i32)> = reachable_target.filter(|_| input.is_down(Action::Mine));
+        self.update_mining(world, mining_target, frame_time);
 
-                for block_x in left_edge as u32..=right_edge as u32 {
-                    let can_jump: bool = SOLIDS.contains(&world.get_block(block_x, block_top_edge as u32));
+        if let Some((block_x, block_y)) = reachable_target.filter(|_| input.pressed(Action::UseItem)) {
+            if let Some(selected_block_id) = self.inventory.selected().map(|item_stack| item_stack.block_id) {
+                let is_placed: bool = if world.blocks.is_wall(selected_block_id) {
+                    world.get_wall(block_x, block_y) == Some(AIR) && world.set_wall(block_x, block_y, selected_block_id).is_some()
+                } else {
+                    let is_replaceable: bool = world.get_block(block_x, block_y)
+                        .and_then(|block_type| world.blocks.get(block_type))
+                        .is_some_and(|block_definition| block_definition.has_flag(BlockFlag::Replaceable));
 
-                    if can_jump {
-                        self.velocity_y = -Player::JUMP_VELOCITY;
+                    // a solid block inside the player would leave it stuck, collision ignores blocks it already overlaps
+                    let is_blocked_by_player: bool = world.blocks.is_solid(selected_block_id) && self.overlaps_block(block_x, block_y);
 
-                        break;
-                    }
+                    is_replaceable && !is_blocked_by_player && world.set_block(block_x, block_y, selected_block_id).is_some()
+                };
+
+                if is_placed {
+                    self.inventory.take_selected();
                 }
             }
         }
-        if ctx.input(|i| i.key_down(egui::Key::A)) {
-            self.velocity_x -= Player::MOVEMENT_ACCELERATION;
+
+        for hotbar_slot in 0..Inventory::HOTBAR_SIZE {
+            if input.pressed(Action::HotbarSlot(hotbar_slot as u8)) {
+                self.inventory.select(hotbar_slot);
+            }
         }
-        if ctx.input(|i| i.key_down(egui::Key::S)) {
-            //
+
+        if input.pressed(Action::HotbarNext) {
+            self.inventory.scroll(1);
         }
-        if ctx.input(|i| i.key_down(egui::Key::D)) {
-            self.velocity_x += Player::MOVEMENT_ACCELERATION;
+        if input.pressed(Action::HotbarPrevious) {
+            self.inventory.scroll(-1);
         }
 
-        self.velocity_x *= 0.90; 
-        self.velocity_y += 0.01;
+        if input.pressed(Action::Jump) {
+            self.jump_requested = true;
+        }
+        self.jump_held = input.is_down(Action::Jump);
 
-        self.velocity_x = self.velocity_x.clamp(-Player::MAX_X_VELOCITY, Player::MAX_X_VELOCITY);
-        self.velocity_y = self.velocity_y.clamp(-Player::MAX_Y_VELOCITY, Player::MAX_Y_VELOCITY);            
+        if input.is_down(Action::MoveDown) {
+            //
+        }
+        self.movement_input = input.value(Action::MoveRight) - input.value(Action::MoveLeft);
+    }
 
-        let mut new_position_x: f32 = self.position_x + self.velocity_x * delta_time as f32;
-        if self.velocity_x > 0.0 {
-            let right_edge: f32 = new_position_x + Player::WIDTH;
-            let right_x: u32 = (right_edge / Renderer::BLOCK_PIXEL_COUNT).floor() as u32;
-            let top_edge: f32 = (self.position_y / Renderer::BLOCK_PIXEL_COUNT).trunc();
-            let bottom_edge: f32 = ((self.position_y + Player::HEIGHT) / Renderer::BLOCK_PIXEL_COUNT).ceil() - 1.0;
+    // advances the player's physics by one fixed length simulation tick
+    pub fn tick(&mut self, world: &World, physics_profile: &PhysicsProfile) {
+        let tick_milliseconds: f32 = 1000.0 / crate::TICKS_PER_SECOND as f32;
 
-            for block_y in top_edge as u32..=bottom_edge as u32 {
-                let is_solid: bool = SOLIDS.contains(&world.get_block(right_x, block_y));
+        self.previous_position_x = self.position_x;
+        self.previous_position_y = self.position_y;
+        self.previous_step_offset = self.step_offset;
 
-                if is_solid {
-                    self.velocity_x = 0.0;
-                    new_position_x = (right_x as f32 * Renderer::BLOCK_PIXEL_COUNT).ceil() - Player::WIDTH;
+        self.step_offset = (self.step_offset - Player::STEP_SMOOTHING_SPEED).max(0.0);
 
-                    break;
-                }
+        if self.jump_requested {
+            self.jump_requested = false;
+
+            if self.contacts.on_ground {
+                self.velocity_y = -physics_profile.jump_velocity;
             }
+        }
+
+        if !self.jump_held {
+            self.velocity_y = self.velocity_y.max(-physics_profile.jump_release_velocity);
+        }
+
+        let (acceleration, friction) = if self.contacts.on_ground {
+            (physics_profile.ground_acceleration, physics_profile.ground_friction)
         } else {
-            let left_edge: f32 = new_position_x;
-            let left_x: u32 = (left_edge / Renderer::BLOCK_PIXEL_COUNT).floor() as u32;
-            let top_edge: f32 = (self.position_y / Renderer::BLOCK_PIXEL_COUNT).trunc();
-            let bottom_edge: f32 = ((self.position_y + Player::HEIGHT) / Renderer::BLOCK_PIXEL_COUNT).ceil() - 1.0;
+            (physics_profile.air_acceleration, physics_profile.air_friction)
+        };
 
-            for block_y in top_edge as u32..=bottom_edge as u32 {
-                let is_solid: bool = SOLIDS.contains(&world.get_block(left_x, block_y));
+        self.velocity_x += self.movement_input * acceleration;
 
-                if is_solid {
-                    self.velocity_x = 0.0;
-                    new_position_x = ((left_x + 1) as f32 * Renderer::BLOCK_PIXEL_COUNT).ceil();
+        self.velocity_x *= friction;
+        self.velocity_y += physics_profile.gravity;
 
-                    break;
-                }
-            }
+        self.velocity_x = self.velocity_x.clamp(-physics_profile.max_x_velocity, physics_profile.max_x_velocity);
+        self.velocity_y = self.velocity_y.min(physics_profile.terminal_velocity);
+
+        let collision: CollisionResult = collision::move_and_collide(
+            world,
+            &self.aabb(),
+            self.velocity_x * tick_milliseconds,
+            self.velocity_y * tick_milliseconds,
+            Player::MAX_STEP_HEIGHT,
+        );
+
+        if collision.collided_x {
+            self.velocity_x = 0.0;
+        }
+        if collision.collided_y {
+            self.velocity_y = 0.0;
         }
 
-        self.position_x = new_position_x;
+        self.position_x = collision.x;
+        self.position_y = collision.y;
+        self.contacts = collision.contacts;
+        self.step_offset += collision.step_height;
+    }
+}
 
-        let mut new_position_y: f32 = self.position_y + self.velocity_y * delta_time as f32;
-        if self.velocity_y > 0.0 {
-            let bottom_edge: f32 = new_position_y + Player::HEIGHT;
-            let bottom_y: u32 = (bottom_edge / Renderer::BLOCK_PIXEL_COUNT).floor() as u32;
-            let left_edge: f32 = (self.position_x / Renderer::BLOCK_PIXEL_COUNT).trunc();
-            let right_edge: f32 = ((self.position_x + Player::WIDTH) / Renderer::BLOCK_PIXEL_COUNT).ceil() - 1.0;
+impl Player {
+    // touching edges do not count as overlapping, so blocks can be placed right next to the player
+    fn overlaps_block(&self, block_x: i32, block_y: i32) -> bool {
+        let block_rect: egui::Rect = Renderer::block_rect(block_x, block_y);
+        let aabb: Aabb = self.aabb();
 
-            for block_x in left_edge as u32..=right_edge as u32 {
-                let is_solid: bool = SOLIDS.contains(&world.get_block(block_x, bottom_y));
+        aabb.x < block_rect.max.x && block_rect.min.x < aabb.x + aabb.width && aabb.y < block_rect.max.y && block_rect.min.y < aabb.y + aabb.height
+    }
 
-                if is_solid {
-                    self.velocity_y = 0.0;
-                    new_position_y = (bottom_y as f32 * Renderer::BLOCK_PIXEL_COUNT).ceil() - Player::HEIGHT;
+    pub fn aabb(&self) -> Aabb {
+        Aabb {
+            x: self.position_x,
+            y: self.position_y,
+            width: Player::WIDTH,
+            height: Player::HEIGHT,
+        }
+    }
 
-                    break;
-                }
-            }
-        } else {
-            let top_edge: f32 = new_position_y;
-            let top_y: u32 = (top_edge / Renderer::BLOCK_PIXEL_COUNT).floor() as u32;
-            let left_edge: f32 = (self.position_x / Renderer::BLOCK_PIXEL_COUNT).trunc();
-            let right_edge: f32 = ((self.position_x + Player::WIDTH) / Renderer::BLOCK_PIXEL_COUNT).ceil() - 1.0;
+    // interpolation from 0.0 at the previous tick to 1.0 at the current tick, includes the step up smoothing
+    pub fn interpolated_position(&self, interpolation: f32) -> (f32, f32) {
+        (
+            self.previous_position_x + (self.position_x - self.previous_position_x) * interpolation,
+            self.previous_position_y + (self.position_y - self.previous_position_y) * interpolation
+                + self.previous_step_offset + (self.step_offset - self.previous_step_offset) * interpolation,
+        )
+    }
 
-            for block_x in left_edge as u32..=right_edge as u32 {
-                let is_solid: bool = SOLIDS.contains(&world.get_block(block_x, top_y));
+    // center of the player in block units
+    pub fn center(&self) -> (f32, f32) {
+        (
+            (self.position_x + Player::WIDTH / 2.0) / Renderer::BLOCK_PIXEL_COUNT,
+            (self.position_y + Player::HEIGHT / 2.0) / Renderer::BLOCK_PIXEL_COUNT,
+        )
+    }
 
-                if is_solid {
-                    self.velocity_y = 0.0;
-                    new_position_y = ((top_y + 1) as f32 * Renderer::BLOCK_PIXEL_COUNT).ceil();
+    pub fn can_reach(&self, world: &World, block_x: i32, block_y: i32) -> bool {
+        let (center_x, center_y) = self.center();
+        let distance: f32 = (block_x as f32 + 0.5 - center_x).hypot(block_y as f32 + 0.5 - center_y);
 
-                    break;
+        distance <= self.reach_distance && world.has_line_of_sight(center_x, center_y, block_x, block_y)
+    }
+
+    // mining speed multiplier of the selected item, items without a tool speed mine at the normal speed
+    pub fn mining_speed(&self, world: &World) -> f32 {
+        self.inventory.selected()
+            .and_then(|item_stack| world.blocks.get(item_stack.block_id))
+            .map_or(1.0, |block_definition| block_definition.tool_speed)
+    }
+
+    // progress is kept while the same block stays targeted and reset when the target changes or mining stops
+    fn update_mining(&mut self, world: &mut World, mining_target: Option<(i32, i32)>, frame_time: f32) {
+        let Some((block_x, block_y)) = mining_target else {
+            self.mining = None;
+            return;
+        };
+
+        let (is_wall, block_type) = match world.get_block(block_x, block_y) {
+            Some(AIR) => (true, world.get_wall(block_x, block_y)),
+            block_type => (false, block_type),
+        };
+
+        let Some(block_definition) = block_type
+            .and_then(|block_type| world.blocks.get(block_type))
+            .filter(|block_definition| !block_definition.has_flag(BlockFlag::Unbreakable)) else {
+            self.mining = None;
+            return;
+        };
+
+        let block_drop: Option<u16> = block_definition.drop;
+        let mining_time: f32 = block_definition.hardness / self.mining_speed(world);
+
+        let mining: &mut MiningProgress = match &mut self.mining {
+            Some(mining) if mining.block_x == block_x && mining.block_y == block_y && mining.is_wall == is_wall => mining,
+            mining => mining.insert(MiningProgress { block_x, block_y, is_wall, progress: 0.0 }),
+        };
+
+        if mining_time > 0.0 {
+            mining.progress += frame_time / mining_time;
+        } else {
+            mining.progress = 1.0;
+        }
+
+        if mining.progress >= 1.0 {
+            // the block stays until its drop fits into the inventory, so nothing is lost while it is full
+            if let Some(drop_block_id) = block_drop {
+                if self.inventory.add(drop_block_id, 1) > 0 {
+                    mining.progress = 1.0;
+                    return;
                 }
             }
-        }
 
-        self.position_y = new_position_y;
+            self.mining = None;
+            if is_wall {
+                world.set_wall(block_x, block_y, AIR);
+            } else {
+                world.set_block(block_x, block_y, AIR);
+            }
+        }
     }
-}
 
-impl Default for Player {
-    fn default() -> Self {
+    pub fn new(world: &World) -> Self {
+        // centered on top of the spawn block
+        let (spawn_x, spawn_y) = world.spawn_point;
+        let position_x: f32 = (spawn_x as f32 + 0.5) * Renderer::BLOCK_PIXEL_COUNT - Player::WIDTH / 2.0;
+        let position_y: f32 = spawn_y as f32 * Renderer::BLOCK_PIXEL_COUNT - Player::HEIGHT;
+
         Self {
-            position_x: World::MAP_WIDTH as f32 / 2.0 * Renderer::BLOCK_PIXEL_COUNT,
+            position_x,
             velocity_x: 0.0,
-            position_y: 50.0 * Renderer::BLOCK_PIXEL_COUNT,
+            position_y,
             velocity_y: 0.0,
+            previous_position_x: position_x,
+            previous_position_y: position_y,
+            movement_input: 0.0,
+            jump_requested: false,
+            jump_held: false,
+            contacts: Contacts::default(),
+            step_offset: 0.0,
+            previous_step_offset: 0.0,
+            inventory: Inventory::default(),
+            mining: None,
+            targeted_block: None,
+            reach_distance: Player::DEFAULT_REACH_DISTANCE,
         }
     }
 }
\ No newline at end of file
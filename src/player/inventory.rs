@@ -0,0 +1,120 @@
+use crate::renderer::block_atlas::BlockAtlas;
+use crate::world::block_registry::BlockRegistry;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ItemStack {
+    pub block_id: u16,
+    pub count: u32,
+}
+
+pub struct Inventory {
+    pub slots: Vec<Option<ItemStack>>,
+    // index into the hotbar, which is the first HOTBAR_SIZE slots
+    pub selected_slot: usize,
+}
+
+impl Inventory {
+    pub const SLOT_COUNT: usize = 36;
+    pub const HOTBAR_SIZE: usize = 9;
+    pub const MAX_STACK_SIZE: u32 = 999;
+
+    const HOTBAR_SLOT_SIZE: f32 = 40.0;
+
+    // fills existing stacks of the same block first, returns the number of items that did not fit
+    pub fn add(&mut self, block_id: u16, mut count: u32) -> u32 {
+        for item_stack in self.slots.iter_mut().flatten() {
+            if item_stack.block_id == block_id {
+                let added_count: u32 = count.min(Self::MAX_STACK_SIZE - item_stack.count);
+                item_stack.count += added_count;
+                count -= added_count;
+            }
+        }
+
+        for slot in self.slots.iter_mut() {
+            if count == 0 {
+                break;
+            }
+            if slot.is_none() {
+                let added_count: u32 = count.min(Self::MAX_STACK_SIZE);
+                *slot = Some(ItemStack { block_id, count: added_count });
+                count -= added_count;
+            }
+        }
+
+        count
+    }
+
+    pub fn selected(&self) -> Option<ItemStack> {
+        self.slots[self.selected_slot]
+    }
+
+    // removes one item from the selected slot and returns its block id
+    pub fn take_selected(&mut self) -> Option<u16> {
+        let slot: &mut Option<ItemStack> = &mut self.slots[self.selected_slot];
+        let item_stack: &mut ItemStack = slot.as_mut()?;
+        let block_id: u16 = item_stack.block_id;
+
+        item_stack.count -= 1;
+        if item_stack.count == 0 {
+            *slot = None;
+        }
+
+        Some(block_id)
+    }
+
+    pub fn select(&mut self, hotbar_slot: usize) {
+        if hotbar_slot < Self::HOTBAR_SIZE {
+            self.selected_slot = hotbar_slot;
+        }
+    }
+
+    // moves the selection by the given number of slots, wrapping around the hotbar
+    pub fn scroll(&mut self, slot_offset: i32) {
+        self.selected_slot = (self.selected_slot as i32 + slot_offset).rem_euclid(Self::HOTBAR_SIZE as i32) as usize;
+    }
+
+    pub fn show_hotbar(&self, ctx: &egui::Context, blocks: &BlockRegistry, block_atlas: &BlockAtlas) {
+        egui::Area::new(egui::Id::new("hotbar"))
+            .anchor(egui::Align2::CENTER_BOTTOM, egui::vec2(0.0, -10.0))
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    for (slot_index, slot) in self.slots[..Self::HOTBAR_SIZE].iter().enumerate() {
+                        let stroke: egui::Stroke = if slot_index == self.selected_slot {
+                            egui::Stroke::new(3.0, egui::Color32::WHITE)
+                        } else {
+                            egui::Stroke::new(1.0, egui::Color32::DARK_GRAY)
+                        };
+
+                        egui::Frame::none()
+                            .fill(egui::Color32::from_black_alpha(150))
+                            .stroke(stroke)
+                            .inner_margin(4.0)
+                            .show(ui, |ui| {
+                                ui.set_min_size(egui::vec2(Self::HOTBAR_SLOT_SIZE, Self::HOTBAR_SLOT_SIZE));
+
+                                if let Some(item_stack) = slot {
+                                    if let Some(uv) = block_atlas.get_uv(item_stack.block_id) {
+                                        let image: egui::Image = egui::Image::new((block_atlas.texture.id(), egui::vec2(Self::HOTBAR_SLOT_SIZE, Self::HOTBAR_SLOT_SIZE))).uv(uv);
+                                        let response: egui::Response = ui.add(image);
+
+                                        if let Some(block_definition) = blocks.get(item_stack.block_id) {
+                                            response.on_hover_text(&block_definition.name);
+                                        }
+                                    }
+                                    ui.label(egui::RichText::new(item_stack.count.to_string()).color(egui::Color32::WHITE));
+                                }
+                            });
+                    }
+                });
+            });
+    }
+}
+
+impl Default for Inventory {
+    fn default() -> Self {
+        Self {
+            slots: vec![None; Self::SLOT_COUNT],
+            selected_slot: 0,
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use std::path::Path;
+use serde::Deserialize;
+
+// tunable movement values, velocities are in pixels per millisecond and accelerations are added every tick
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PhysicsProfile {
+    pub ground_acceleration: f32,
+    pub air_acceleration: f32,
+    // horizontal velocity is multiplied by this every tick
+    pub ground_friction: f32,
+    pub air_friction: f32,
+    pub gravity: f32,
+    pub max_x_velocity: f32,
+    // maximum falling speed
+    pub terminal_velocity: f32,
+    pub jump_velocity: f32,
+    // upward speed is cut down to this when jump is released early, so tapping jump gives a shorter jump
+    pub jump_release_velocity: f32,
+}
+
+impl PhysicsProfile {
+    pub const DEFAULT_PATH: &'static str = "assets/physics.ron";
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text: String = std::fs::read_to_string(path).map_err(|error| format!("Failed to read {}: {}", path.display(), error))?;
+
+        let physics_profile: PhysicsProfile = ron::from_str(&text).map_err(|error| format!("Invalid physics profile in {}: {}", path.display(), error))?;
+        physics_profile.validate().map_err(|error| format!("Invalid physics profile in {}: {}", path.display(), error))?;
+
+        Ok(physics_profile)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, friction) in [("ground_friction", self.ground_friction), ("air_friction", self.air_friction)] {
+            if !(0.0..=1.0).contains(&friction) {
+                return Err(format!("{} must be between 0.0 and 1.0", name));
+            }
+        }
+        if self.max_x_velocity <= 0.0 || self.terminal_velocity <= 0.0 {
+            return Err("max_x_velocity and terminal_velocity must be positive".to_string());
+        }
+        if self.jump_release_velocity < 0.0 || self.jump_release_velocity > self.jump_velocity {
+            return Err("jump_release_velocity must be between 0.0 and jump_velocity".to_string());
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for PhysicsProfile {
+    fn default() -> Self {
+        Self {
+            ground_acceleration: 0.01,
+            air_acceleration: 0.005,
+            ground_friction: 0.90,
+            air_friction: 0.95,
+            gravity: 0.01,
+            max_x_velocity: 0.2,
+            terminal_velocity: 2.0,
+            jump_velocity: 0.45,
+            jump_release_velocity: 0.15,
+        }
+    }
+}
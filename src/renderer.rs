@@ -1,5 +1,191 @@
-pub struct Renderer;
+pub mod block_atlas;
+pub mod camera;
+pub mod frame_stats;
+
+use std::collections::{HashMap, HashSet};
+use eframe::egui;
+use block_atlas::BlockAtlas;
+use camera::Camera;
+use crate::world::World;
+use crate::world::block_registry::AIR;
+use crate::world::chunk::Chunk;
+use crate::world::lighting::MAX_LIGHT;
+
+// passes drawn from back to front
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RenderLayer {
+    // walls and sky
+    Background,
+    Blocks,
+    Entities,
+    Overlay,
+}
+
+impl RenderLayer {
+    pub const ALL: [RenderLayer; 4] = [RenderLayer::Background, RenderLayer::Blocks, RenderLayer::Entities, RenderLayer::Overlay];
+}
+
+// positions and sizes are in world pixels, the camera transform is applied when the layers are drawn
+pub enum DrawCommand {
+    Rect { rect: egui::Rect, color: egui::Color32 },
+    // part of the block atlas, uv as returned by BlockAtlas
+    AtlasRect { rect: egui::Rect, uv: egui::Rect },
+    // border drawn on the inside of the rect
+    Outline { rect: egui::Rect, width: f32, color: egui::Color32 },
+}
+
+// the cached meshes of one chunk in world pixels
+struct ChunkMeshes {
+    // drawn in RenderLayer::Background
+    background: egui::Mesh,
+    // drawn in RenderLayer::Blocks
+    blocks: egui::Mesh,
+}
+
+// collects draw commands during a frame and draws them layer by layer, terrain is drawn from meshes cached per chunk
+#[derive(Default)]
+pub struct Renderer {
+    commands: [Vec<DrawCommand>; RenderLayer::ALL.len()],
+    // terrain meshes, removed when a block in the chunk changes or the chunk leaves the view
+    chunk_meshes: HashMap<(i32, i32), ChunkMeshes>,
+    // chunk meshes built during the last call to render
+    pub rebuilt_chunk_mesh_count: usize,
+}
 
 impl Renderer {
+    // size of a block in world pixels, the camera zoom scales this to screen pixels
     pub const BLOCK_PIXEL_COUNT: f32 = 30.0;
-}
\ No newline at end of file
+    // walls are drawn darker than blocks so they read as background
+    const WALL_BRIGHTNESS: f32 = 0.5;
+    // cached chunk meshes further than this many chunks outside of the view are dropped
+    const CHUNK_CACHE_MARGIN: i32 = 2;
+
+    pub fn block_rect(block_x: i32, block_y: i32) -> egui::Rect {
+        egui::Rect::from_min_size(
+            egui::pos2(block_x as f32 * Self::BLOCK_PIXEL_COUNT, block_y as f32 * Self::BLOCK_PIXEL_COUNT),
+            egui::Vec2::splat(Self::BLOCK_PIXEL_COUNT),
+        )
+    }
+
+    pub fn submit(&mut self, layer: RenderLayer, command: DrawCommand) {
+        self.commands[layer as usize].push(command);
+    }
+
+    pub fn invalidate_chunks(&mut self, chunk_positions: &HashSet<(i32, i32)>) {
+        for chunk_position in chunk_positions {
+            self.chunk_meshes.remove(chunk_position);
+        }
+    }
+
+    pub fn cached_chunk_mesh_count(&self) -> usize {
+        self.chunk_meshes.len()
+    }
+
+    // draws every layer and clears the submitted commands for the next frame
+    pub fn render(&mut self, painter: &egui::Painter, camera: &Camera, world: &World, block_atlas: &BlockAtlas) {
+        let transform: egui::emath::TSTransform = camera.transform();
+
+        let (min_block_x, min_block_y, max_block_x, max_block_y) = camera.visible_blocks();
+        let (min_chunk_x, min_chunk_y) = World::chunk_position(min_block_x, min_block_y);
+        let (max_chunk_x, max_chunk_y) = World::chunk_position(max_block_x, max_block_y);
+
+        self.rebuilt_chunk_mesh_count = 0;
+        self.chunk_meshes.retain(|&(chunk_x, chunk_y), _| {
+            chunk_x >= min_chunk_x - Self::CHUNK_CACHE_MARGIN && chunk_x <= max_chunk_x + Self::CHUNK_CACHE_MARGIN
+                && chunk_y >= min_chunk_y - Self::CHUNK_CACHE_MARGIN && chunk_y <= max_chunk_y + Self::CHUNK_CACHE_MARGIN
+        });
+
+        for chunk_x in min_chunk_x..=max_chunk_x {
+            for chunk_y in min_chunk_y..=max_chunk_y {
+                self.chunk_meshes.entry((chunk_x, chunk_y)).or_insert_with(|| {
+                    self.rebuilt_chunk_mesh_count += 1;
+                    Self::build_chunk_meshes(world, block_atlas, chunk_x, chunk_y)
+                });
+            }
+        }
+
+        for layer in RenderLayer::ALL {
+            let mut textured_mesh: egui::Mesh = egui::Mesh::with_texture(block_atlas.texture.id());
+            let mut colored_mesh: egui::Mesh = egui::Mesh::default();
+
+            if layer == RenderLayer::Background || layer == RenderLayer::Blocks {
+                for chunk_x in min_chunk_x..=max_chunk_x {
+                    for chunk_y in min_chunk_y..=max_chunk_y {
+                        if let Some(chunk_meshes) = self.chunk_meshes.get(&(chunk_x, chunk_y)) {
+                            textured_mesh.append_ref(if layer == RenderLayer::Background { &chunk_meshes.background } else { &chunk_meshes.blocks });
+                        }
+                    }
+                }
+            }
+
+            for command in self.commands[layer as usize].drain(..) {
+                match command {
+                    DrawCommand::Rect { rect, color } => colored_mesh.add_colored_rect(rect, color),
+                    DrawCommand::AtlasRect { rect, uv } => textured_mesh.add_rect_with_uv(rect, uv, egui::Color32::WHITE),
+                    DrawCommand::Outline { rect, width, color } => {
+                        let inner_rect: egui::Rect = rect.shrink(width);
+
+                        colored_mesh.add_colored_rect(egui::Rect::from_x_y_ranges(rect.x_range(), rect.top()..=inner_rect.top()), color);
+                        colored_mesh.add_colored_rect(egui::Rect::from_x_y_ranges(rect.x_range(), inner_rect.bottom()..=rect.bottom()), color);
+                        colored_mesh.add_colored_rect(egui::Rect::from_x_y_ranges(rect.left()..=inner_rect.left(), inner_rect.y_range()), color);
+                        colored_mesh.add_colored_rect(egui::Rect::from_x_y_ranges(inner_rect.right()..=rect.right(), inner_rect.y_range()), color);
+                    },
+                }
+            }
+
+            for mut mesh in [textured_mesh, colored_mesh] {
+                if !mesh.is_empty() {
+                    mesh.transform(transform);
+                    painter.add(egui::Shape::Mesh(mesh));
+                }
+            }
+        }
+    }
+
+    // one quad per block inside the map in world pixels and one per wall or sky cell behind it, darkened by the light level of the block
+    fn build_chunk_meshes(world: &World, block_atlas: &BlockAtlas, chunk_x: i32, chunk_y: i32) -> ChunkMeshes {
+        let mut background_mesh: egui::Mesh = egui::Mesh::with_texture(block_atlas.texture.id());
+        let mut block_mesh: egui::Mesh = egui::Mesh::with_texture(block_atlas.texture.id());
+        let sky_uv: Option<egui::Rect> = block_atlas.get_uv(AIR);
+
+        for local_y in 0..Chunk::SIZE as i32 {
+            for local_x in 0..Chunk::SIZE as i32 {
+                let (block_x, block_y) = (chunk_x * Chunk::SIZE as i32 + local_x, chunk_y * Chunk::SIZE as i32 + local_y);
+
+                let Some(block_type) = world.get_block(block_x, block_y) else {
+                    continue;
+                };
+
+                let wall_type: u16 = world.get_wall(block_x, block_y).unwrap_or(AIR);
+                let block_rect: egui::Rect = Self::block_rect(block_x, block_y);
+
+                let brightness: f32 = world.get_light(block_x, block_y) as f32 / MAX_LIGHT as f32;
+                let color: egui::Color32 = egui::Color32::from_gray((brightness * 255.0) as u8);
+
+                // blocks that can be walked through may be partly transparent, so the wall or the sky is drawn behind them
+                if !world.blocks.is_solid(block_type) {
+                    let background: Option<(egui::Rect, egui::Color32)> = if wall_type != AIR {
+                        block_atlas.get_uv(wall_type).map(|uv| (uv, egui::Color32::from_gray((brightness * Self::WALL_BRIGHTNESS * 255.0) as u8)))
+                    } else {
+                        sky_uv.map(|uv| (uv, color))
+                    };
+
+                    if let Some((uv, background_color)) = background {
+                        background_mesh.add_rect_with_uv(block_rect, uv, background_color);
+                    }
+                }
+
+                if block_type != AIR {
+                    if let Some(uv) = block_atlas.get_uv(block_type) {
+                        block_mesh.add_rect_with_uv(block_rect, uv, color);
+                    }
+                }
+            }
+        }
+
+        ChunkMeshes {
+            background: background_mesh,
+            blocks: block_mesh,
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use std::fs::File;
+use std::path::Path;
+use crate::renderer::Renderer;
+use crate::world::block_registry::BlockRegistry;
+
+// every block texture decoded once and packed into a single gpu texture
+pub struct BlockAtlas {
+    pub texture: egui::TextureHandle,
+    // uv rectangle of each block texture, indexed by block id
+    uvs: Vec<Option<egui::Rect>>,
+    // uv rectangles of the mining crack overlay, from least to most damaged
+    crack_uvs: Vec<egui::Rect>,
+}
+
+impl BlockAtlas {
+    pub const TEXTURE_DIRECTORY: &'static str = "assets/textures";
+    // textures are resampled so one texture pixel maps to one screen pixel
+    pub const TILE_SIZE: usize = Renderer::BLOCK_PIXEL_COUNT as usize;
+    pub const CRACK_STAGE_COUNT: usize = 4;
+
+    pub fn new(ctx: &egui::Context, blocks: &BlockRegistry, texture_directory: &Path) -> Self {
+        let block_ids: Vec<u16> = blocks.ids().collect();
+
+        let mut texture_names: Vec<String> = block_ids.iter()
+            .filter_map(|&block_id| blocks.get(block_id))
+            .map(|block_definition| block_definition.texture.clone())
+            .collect();
+        texture_names.extend((1..=Self::CRACK_STAGE_COUNT).map(|crack_stage| format!("crack_{}", crack_stage)));
+
+        let columns: usize = (texture_names.len() as f32).sqrt().ceil().max(1.0) as usize;
+        let rows: usize = texture_names.len().div_ceil(columns).max(1);
+        let atlas_size: [usize; 2] = [columns * Self::TILE_SIZE, rows * Self::TILE_SIZE];
+
+        let mut image: egui::ColorImage = egui::ColorImage::new(atlas_size, egui::Color32::TRANSPARENT);
+        let mut tile_uvs: Vec<egui::Rect> = Vec::new();
+
+        for (tile_index, texture_name) in texture_names.iter().enumerate() {
+            let (tile_x, tile_y) = (tile_index % columns * Self::TILE_SIZE, tile_index / columns * Self::TILE_SIZE);
+
+            let texture_path: std::path::PathBuf = texture_directory.join(format!("{}.png", texture_name));
+            let texture: egui::ColorImage = match load_png(&texture_path) {
+                Ok(texture) => texture,
+                Err(error) => {
+                    println!("Failed to load texture {}: {}", texture_path.display(), error);
+                    missing_texture()
+                },
+            };
+
+            // nearest neighbour sampling keeps the pixel art sharp at any texture resolution
+            for pixel_y in 0..Self::TILE_SIZE {
+                for pixel_x in 0..Self::TILE_SIZE {
+                    let texture_x: usize = pixel_x * texture.size[0] / Self::TILE_SIZE;
+                    let texture_y: usize = pixel_y * texture.size[1] / Self::TILE_SIZE;
+
+                    image[(tile_x + pixel_x, tile_y + pixel_y)] = texture[(texture_x, texture_y)];
+                }
+            }
+
+            tile_uvs.push(egui::Rect::from_min_size(
+                egui::pos2(tile_x as f32 / atlas_size[0] as f32, tile_y as f32 / atlas_size[1] as f32),
+                egui::vec2(Self::TILE_SIZE as f32 / atlas_size[0] as f32, Self::TILE_SIZE as f32 / atlas_size[1] as f32),
+            ));
+        }
+
+        let crack_uvs: Vec<egui::Rect> = tile_uvs.split_off(block_ids.len());
+
+        let mut uvs: Vec<Option<egui::Rect>> = Vec::new();
+        for (&block_id, uv) in block_ids.iter().zip(tile_uvs) {
+            if uvs.len() <= block_id as usize {
+                uvs.resize(block_id as usize + 1, None);
+            }
+            uvs[block_id as usize] = Some(uv);
+        }
+
+        Self {
+            texture: ctx.load_texture("block_atlas", image, egui::TextureOptions::NEAREST),
+            uvs,
+            crack_uvs,
+        }
+    }
+
+    pub fn get_uv(&self, block_id: u16) -> Option<egui::Rect> {
+        *self.uvs.get(block_id as usize)?
+    }
+
+    // progress from 0.0 to 1.0, returns None before the first crack stage is reached
+    pub fn get_crack_uv(&self, progress: f32) -> Option<egui::Rect> {
+        let crack_stage: usize = (progress * (Self::CRACK_STAGE_COUNT + 1) as f32).floor() as usize;
+
+        self.crack_uvs.get(crack_stage.checked_sub(1)?).copied()
+    }
+}
+
+fn load_png(path: &Path) -> Result<egui::ColorImage, String> {
+    let mut decoder: png::Decoder<File> = png::Decoder::new(File::open(path).map_err(|error| error.to_string())?);
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+
+    let mut reader: png::Reader<File> = decoder.read_info().map_err(|error| error.to_string())?;
+    let mut buffer: Vec<u8> = vec![0; reader.output_buffer_size()];
+    let frame: png::OutputInfo = reader.next_frame(&mut buffer).map_err(|error| error.to_string())?;
+
+    if frame.width != frame.height || frame.width == 0 {
+        return Err(format!("texture must be square, got {}x{}", frame.width, frame.height));
+    }
+
+    let bytes: &[u8] = &buffer[..frame.buffer_size()];
+    let pixels: Vec<egui::Color32> = match frame.color_type {
+        png::ColorType::Rgba => bytes.chunks_exact(4).map(|pixel| egui::Color32::from_rgba_unmultiplied(pixel[0], pixel[1], pixel[2], pixel[3])).collect(),
+        png::ColorType::Rgb => bytes.chunks_exact(3).map(|pixel| egui::Color32::from_rgb(pixel[0], pixel[1], pixel[2])).collect(),
+        png::ColorType::GrayscaleAlpha => bytes.chunks_exact(2).map(|pixel| egui::Color32::from_rgba_unmultiplied(pixel[0], pixel[0], pixel[0], pixel[1])).collect(),
+        png::ColorType::Grayscale => bytes.iter().map(|&pixel| egui::Color32::from_gray(pixel)).collect(),
+        png::ColorType::Indexed => return Err("unexpanded indexed color".to_string()),
+    };
+
+    Ok(egui::ColorImage {
+        size: [frame.width as usize, frame.height as usize],
+        pixels,
+    })
+}
+
+// magenta and black checkerboard, shown in place of textures that could not be loaded
+fn missing_texture() -> egui::ColorImage {
+    let mut texture: egui::ColorImage = egui::ColorImage::new([2, 2], egui::Color32::BLACK);
+    texture[(0, 0)] = egui::Color32::from_rgb(255, 0, 255);
+    texture[(1, 1)] = egui::Color32::from_rgb(255, 0, 255);
+
+    texture
+}
@@ -0,0 +1,88 @@
+use eframe::egui;
+use crate::renderer::Renderer;
+use crate::world::World;
+
+// the only place where world positions in pixels are converted to screen positions and back
+pub struct Camera {
+    // world position in pixels shown at the center of the viewport
+    pub position_x: f32,
+    pub position_y: f32,
+    // screen pixels per world pixel
+    pub zoom: f32,
+    // area of the screen the world is drawn into
+    pub viewport: egui::Rect,
+}
+
+impl Camera {
+    pub const MIN_ZOOM: f32 = 0.5;
+    pub const MAX_ZOOM: f32 = 3.0;
+    // how quickly the camera catches up with its target, higher is tighter
+    const FOLLOW_SPEED: f32 = 10.0;
+
+    pub fn new(position_x: f32, position_y: f32) -> Self {
+        Self {
+            position_x,
+            position_y,
+            zoom: 1.0,
+            viewport: egui::Rect::ZERO,
+        }
+    }
+
+    // moves toward the target, the fraction covered per frame does not depend on the frame rate
+    pub fn update(&mut self, world: &World, viewport: egui::Rect, target_x: f32, target_y: f32, zoom_factor: f32, frame_time: f32) {
+        self.viewport = viewport;
+        self.zoom = (self.zoom * zoom_factor).clamp(Self::MIN_ZOOM, Self::MAX_ZOOM);
+
+        let follow_amount: f32 = 1.0 - (-Self::FOLLOW_SPEED * frame_time).exp();
+        self.position_x += (target_x - self.position_x) * follow_amount;
+        self.position_y += (target_y - self.position_y) * follow_amount;
+
+        self.clamp_to_world(world);
+    }
+
+    // keeps the view inside the map, a map smaller than the view is centered
+    fn clamp_to_world(&mut self, world: &World) {
+        let half_view_width: f32 = self.viewport.width() / 2.0 / self.zoom;
+        let half_view_height: f32 = self.viewport.height() / 2.0 / self.zoom;
+        let world_width: f32 = world.width as f32 * Renderer::BLOCK_PIXEL_COUNT;
+        let world_height: f32 = world.height as f32 * Renderer::BLOCK_PIXEL_COUNT;
+
+        self.position_x = if world_width > half_view_width * 2.0 {
+            self.position_x.clamp(half_view_width, world_width - half_view_width)
+        } else {
+            world_width / 2.0
+        };
+        self.position_y = if world_height > half_view_height * 2.0 {
+            self.position_y.clamp(half_view_height, world_height - half_view_height)
+        } else {
+            world_height / 2.0
+        };
+    }
+
+    // world pixels to screen pixels, the translation is rounded to whole screen pixels so textures do not shimmer while moving
+    pub fn transform(&self) -> egui::emath::TSTransform {
+        let translation: egui::Vec2 = self.viewport.center().to_vec2() - egui::vec2(self.position_x, self.position_y) * self.zoom;
+
+        egui::emath::TSTransform::new(translation.round(), self.zoom)
+    }
+
+    pub fn screen_to_world(&self, screen_position: egui::Pos2) -> (f32, f32) {
+        let world_position: egui::Pos2 = self.transform().inverse() * screen_position;
+
+        (world_position.x, world_position.y)
+    }
+
+    pub fn screen_to_block(&self, screen_position: egui::Pos2) -> (i32, i32) {
+        let (world_x, world_y) = self.screen_to_world(screen_position);
+
+        ((world_x / Renderer::BLOCK_PIXEL_COUNT).floor() as i32, (world_y / Renderer::BLOCK_PIXEL_COUNT).floor() as i32)
+    }
+
+    // inclusive range of block coordinates touching the viewport, as (min_x, min_y, max_x, max_y)
+    pub fn visible_blocks(&self) -> (i32, i32, i32, i32) {
+        let (min_block_x, min_block_y) = self.screen_to_block(self.viewport.min);
+        let (max_block_x, max_block_y) = self.screen_to_block(self.viewport.max);
+
+        (min_block_x, min_block_y, max_block_x, max_block_y)
+    }
+}
@@ -0,0 +1,42 @@
+use std::collections::VecDeque;
+
+// rolling frame and render times for the debug overlay, in seconds
+#[derive(Default)]
+pub struct FrameStats {
+    frame_times: VecDeque<f32>,
+    render_times: VecDeque<f32>,
+}
+
+impl FrameStats {
+    // about two seconds at 60 frames per second
+    const SAMPLE_COUNT: usize = 120;
+
+    pub fn record(&mut self, frame_time: f32, render_time: f32) {
+        for (samples, time) in [(&mut self.frame_times, frame_time), (&mut self.render_times, render_time)] {
+            if samples.len() == Self::SAMPLE_COUNT {
+                samples.pop_front();
+            }
+            samples.push_back(time);
+        }
+    }
+
+    pub fn average_frame_time(&self) -> f32 {
+        Self::average(&self.frame_times)
+    }
+
+    pub fn max_frame_time(&self) -> f32 {
+        self.frame_times.iter().copied().fold(0.0, f32::max)
+    }
+
+    pub fn average_render_time(&self) -> f32 {
+        Self::average(&self.render_times)
+    }
+
+    fn average(samples: &VecDeque<f32>) -> f32 {
+        if samples.is_empty() {
+            return 0.0;
+        }
+
+        samples.iter().sum::<f32>() / samples.len() as f32
+    }
+}
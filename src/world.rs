@@ -1,72 +1,387 @@
-mod world_generator;
-pub mod block_textures;
-pub mod block_data;
+pub mod world_generator;
+mod world_file;
+pub mod block_registry;
+pub mod chunk;
+pub mod collision;
+pub mod lighting;
 
-use crate::Renderer;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::Arc;
 use crate::Player;
+use chunk::Chunk;
+use lighting::MAX_LIGHT;
+use block_registry::{BlockRegistry, AIR};
 
 pub struct World {
-    pub world_map: Box<[u16; (Self::MAP_WIDTH * Self::MAP_HEIGHT) as usize]>,
+    pub width: u32,
+    pub height: u32,
+    pub seed: u64,
+    pub blocks: Arc<BlockRegistry>,
+    // block the player spawns on top of, set by world generation and not saved since loaded worlds keep the player
+    pub spawn_point: (i32, i32),
+    // chunks are only allocated once a non-air block is written to them
+    chunks: HashMap<(i32, i32), Chunk>,
+    // compressed chunks outside of the loaded columns, see update_loaded_chunks
+    unloaded_chunks: HashMap<(i32, i32), Vec<u8>>,
+    // chunks with blocks changed since the last call to take_changed_chunks
+    changed_chunks: HashSet<(i32, i32)>,
+    // set once the world is complete, false while it is being generated or loaded so writes do not relight
+    is_lit: bool,
 }
 
 impl World {
     pub const MAP_WIDTH: u32 = 4000;
     pub const MAP_HEIGHT: u32 = 500;
+    // coordinates are i32, these keep every coordinate inside of it and a generated world within a few hundred MB
+    pub const MAX_MAP_WIDTH: u32 = 50_000;
+    pub const MAX_MAP_HEIGHT: u32 = 5_000;
+    // chunk columns kept loaded on each side of the view
+    pub const LOADED_CHUNK_MARGIN: i32 = 2;
 
-    pub fn update_frame_mesh(&self, player: &Player, mesh: &mut egui::Mesh, window_width: f32, window_height: f32) {
-        let texture_pixel_width: f32 = Renderer::BLOCK_PIXEL_COUNT / 5.0;
-                
-        let screen_block_count_x: f32 = window_width / Renderer::BLOCK_PIXEL_COUNT;
-        let screen_block_count_y: f32 = window_height / Renderer::BLOCK_PIXEL_COUNT;
-
-        let player_block_x: i32 = (player.position_x / Renderer::BLOCK_PIXEL_COUNT).trunc() as i32;
-        let player_block_y: i32 = (player.position_y / Renderer::BLOCK_PIXEL_COUNT).trunc() as i32;
-
-        for relative_x in (-screen_block_count_x / 2.0).floor() as i32..(screen_block_count_x / 2.0).ceil() as i32 + 1 {
-            for relative_y in (-screen_block_count_y / 2.0).floor() as i32..(screen_block_count_y / 2.0).ceil() as i32 + 1 {
-                for texture_x in 0..5 {
-                    for texture_y in 0..5 {
-
-                        let (block_x, block_y) = (relative_x + player_block_x, relative_y + player_block_y);
-                        let block_type: u16 = self.get_block(block_x as u32, block_y as u32);
-                        let block_texture: &[[&str; 5]; 5] = block_textures::TEXTURE_MAP[block_type as usize];
-                        let block_coordinates: (f32, f32) = (
-                            texture_x as f32 * texture_pixel_width + relative_x as f32 * Renderer::BLOCK_PIXEL_COUNT - ((player.position_x / Renderer::BLOCK_PIXEL_COUNT).fract() * Renderer::BLOCK_PIXEL_COUNT - window_width / 2.0).floor(),
-                            texture_y as f32 * texture_pixel_width + relative_y as f32 * Renderer::BLOCK_PIXEL_COUNT - ((player.position_y / Renderer::BLOCK_PIXEL_COUNT).fract() * Renderer::BLOCK_PIXEL_COUNT - window_height / 2.0).floor(),
-                        );
-
-                        mesh.add_colored_rect(
-                            egui::Rect::from_x_y_ranges(
-                                block_coordinates.0..=block_coordinates.0 + texture_pixel_width, 
-                                block_coordinates.1..=block_coordinates.1 + texture_pixel_width
-                            ),
-                            match egui::Color32::from_hex(block_texture[texture_y][texture_x]) {
-                                Ok(color) => color,
-                                Err(_) => {
-                                    println!("Invalid HEX string {}", block_texture[texture_y][texture_x]);
-                                    egui::Color32::BLACK
-                                },
-                            },
-                        );
-                    }
+    pub fn new(width: u32, height: u32, seed: u64, blocks: Arc<BlockRegistry>) -> Self {
+        Self {
+            width,
+            height,
+            seed,
+            blocks,
+            spawn_point: (width as i32 / 2, 50),
+            chunks: HashMap::new(),
+            unloaded_chunks: HashMap::new(),
+            changed_chunks: HashSet::new(),
+            is_lit: false,
+        }
+    }
+
+    pub fn is_in_bounds(&self, x: i32, y: i32) -> bool {
+        x >= 0 && y >= 0 && (x as u32) < self.width && (y as u32) < self.height
+    }
+
+    // returns None for coordinates outside of the map
+    pub fn get_block(&self, x: i32, y: i32) -> Option<u16> {
+        if !self.is_in_bounds(x, y) {
+            return None;
+        }
+
+        Some(self.read_cell(x, y, Chunk::get_block, AIR))
+    }
+
+    // returns the replaced block, or None if the coordinates are outside of the map or the block id is unknown and nothing was written
+    pub fn set_block(&mut self, x: i32, y: i32, block_type: u16) -> Option<u16> {
+        if !self.blocks.contains(block_type) {
+            println!("Error: Unknown block id {}", block_type);
+            return None;
+        }
+        if self.blocks.is_wall(block_type) {
+            println!("Error: Wall id {} written to the block layer", block_type);
+            return None;
+        }
+
+        let previous_block: u16 = self.get_block(x, y)?;
+        self.write_cell(x, y, block_type, previous_block, Chunk::set_block);
+
+        Some(previous_block)
+    }
+
+    // returns None for coordinates outside of the map, AIR where there is no wall
+    pub fn get_wall(&self, x: i32, y: i32) -> Option<u16> {
+        if !self.is_in_bounds(x, y) {
+            return None;
+        }
+
+        Some(self.read_cell(x, y, Chunk::get_wall, AIR))
+    }
+
+    // returns the replaced wall, or None if the coordinates are outside of the map or the id is not a wall and nothing was written
+    pub fn set_wall(&mut self, x: i32, y: i32, wall_type: u16) -> Option<u16> {
+        if wall_type != AIR && !self.blocks.is_wall(wall_type) {
+            println!("Error: Block id {} written to the wall layer", wall_type);
+            return None;
+        }
+
+        let previous_wall: u16 = self.get_wall(x, y)?;
+        self.write_cell(x, y, wall_type, previous_wall, Chunk::set_wall);
+
+        Some(previous_wall)
+    }
+
+    // shared by the block, wall and light layers, the coordinates must be inside of the map,
+    // empty_value is the value of every cell of a chunk that is not allocated
+    fn read_cell<T>(&self, x: i32, y: i32, read: fn(&Chunk, u32, u32) -> T, empty_value: T) -> T {
+        let chunk_position: (i32, i32) = Self::chunk_position(x, y);
+        let (local_x, local_y) = (x as u32 % Chunk::SIZE, y as u32 % Chunk::SIZE);
+
+        if let Some(chunk) = self.chunks.get(&chunk_position) {
+            return read(chunk, local_x, local_y);
+        }
+
+        // slow, but only reached when something far outside of the view is read, like a save
+        match self.unloaded_chunks.get(&chunk_position) {
+            Some(compressed_chunk) => read(&Chunk::decompress(compressed_chunk), local_x, local_y),
+            None => empty_value,
+        }
+    }
+
+    fn write_cell(&mut self, x: i32, y: i32, value: u16, previous_value: u16, write: fn(&mut Chunk, u32, u32, u16)) {
+        let chunk_position: (i32, i32) = Self::chunk_position(x, y);
+        let (local_x, local_y) = (x as u32 % Chunk::SIZE, y as u32 % Chunk::SIZE);
+
+        self.load_chunk(chunk_position);
+
+        if value == AIR {
+            if let Some(chunk) = self.chunks.get_mut(&chunk_position) {
+                write(chunk, local_x, local_y, value);
+
+                if chunk.is_empty() {
+                    self.chunks.remove(&chunk_position);
                 }
             }
+        } else {
+            write(self.chunks.entry(chunk_position).or_default(), local_x, local_y, value);
+        }
+
+        if value != previous_value {
+            self.mark_changed(x, y);
+            self.relight_around(x, y);
+        }
+    }
+
+    // everything outside of the map counts as solid so entities can not leave it
+    pub fn is_solid(&self, x: i32, y: i32) -> bool {
+        match self.get_block(x, y) {
+            Some(block_type) => self.blocks.is_solid(block_type),
+            None => true,
+        }
+    }
+
+    // walks every block crossed by the line from the start point (in block units) to the center of the target block,
+    // the target block itself and the block containing the start point do not block the line
+    pub fn has_line_of_sight(&self, start_x: f32, start_y: f32, target_x: i32, target_y: i32) -> bool {
+        let (direction_x, direction_y) = (target_x as f32 + 0.5 - start_x, target_y as f32 + 0.5 - start_y);
+        let (step_x, step_y) = (direction_x.signum() as i32, direction_y.signum() as i32);
+
+        // distance along the line, as a fraction of its length, needed to cross one block on each axis
+        let delta_x: f32 = if direction_x != 0.0 { 1.0 / direction_x.abs() } else { f32::INFINITY };
+        let delta_y: f32 = if direction_y != 0.0 { 1.0 / direction_y.abs() } else { f32::INFINITY };
+
+        let (mut block_x, mut block_y) = (start_x.floor() as i32, start_y.floor() as i32);
+        let mut next_x: f32 = if direction_x > 0.0 { (block_x as f32 + 1.0 - start_x) * delta_x } else { (start_x - block_x as f32) * delta_x };
+        let mut next_y: f32 = if direction_y > 0.0 { (block_y as f32 + 1.0 - start_y) * delta_y } else { (start_y - block_y as f32) * delta_y };
+
+        while (block_x, block_y) != (target_x, target_y) {
+            if next_x.min(next_y) > 1.0 {
+                break;
+            }
+
+            if next_x < next_y {
+                block_x += step_x;
+                next_x += delta_x;
+            } else {
+                block_y += step_y;
+                next_y += delta_y;
+            }
+
+            if (block_x, block_y) != (target_x, target_y) && self.is_solid(block_x, block_y) {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    pub fn chunk_position(x: i32, y: i32) -> (i32, i32) {
+        (x.div_euclid(Chunk::SIZE as i32), y.div_euclid(Chunk::SIZE as i32))
+    }
+
+    // a block on the edge of a chunk also changes the neighbouring chunks, whose meshes can depend on the blocks around them
+    fn mark_changed(&mut self, x: i32, y: i32) {
+        let (local_x, local_y) = (x.rem_euclid(Chunk::SIZE as i32), y.rem_euclid(Chunk::SIZE as i32));
+        let is_on_edge: bool = local_x == 0 || local_y == 0 || local_x == Chunk::SIZE as i32 - 1 || local_y == Chunk::SIZE as i32 - 1;
+
+        if !is_on_edge {
+            self.changed_chunks.insert(Self::chunk_position(x, y));
+            return;
+        }
+
+        for offset_x in -1..=1 {
+            for offset_y in -1..=1 {
+                self.changed_chunks.insert(Self::chunk_position(x + offset_x, y + offset_y));
+            }
         }
     }
 
-    pub fn get_block(&self, x: u32, y: u32) -> u16 {
-        self.world_map[(x + y * Self::MAP_WIDTH) as usize]
+    pub fn calculate_lighting(&mut self) {
+        lighting::calculate(self);
+        self.is_lit = true;
     }
 
-    pub fn set_block(&mut self, x: u32, y: u32, block_type: u16) -> () {
-        self.world_map[(x + y * Self::MAP_WIDTH) as usize] = block_type;
+    // fully lit until the lighting has been calculated
+    pub fn get_light(&self, x: i32, y: i32) -> u8 {
+        if self.is_lit { self.read_light(x, y) } else { MAX_LIGHT }
+    }
+
+    // the stored light level, outside of the map is fully lit
+    fn read_light(&self, x: i32, y: i32) -> u8 {
+        if !self.is_in_bounds(x, y) {
+            return MAX_LIGHT;
+        }
+
+        self.read_cell(x, y, Chunk::get_light, MAX_LIGHT)
+    }
+
+    // only allocates a chunk for light below MAX_LIGHT, the coordinates must be inside of the map
+    fn write_light(&mut self, x: i32, y: i32, level: u8) {
+        let chunk_position: (i32, i32) = Self::chunk_position(x, y);
+        let (local_x, local_y) = (x as u32 % Chunk::SIZE, y as u32 % Chunk::SIZE);
+
+        self.load_chunk(chunk_position);
+        if level == MAX_LIGHT {
+            if let Some(chunk) = self.chunks.get_mut(&chunk_position) {
+                chunk.set_light(local_x, local_y, level);
+            }
+        } else {
+            self.chunks.entry(chunk_position).or_default().set_light(local_x, local_y, level);
+        }
+    }
+
+    // light can travel at most MAX_LIGHT blocks, so a change can only affect blocks that close to it
+    // or to the column below it, where sunlight may have started or stopped reaching
+    fn relight_around(&mut self, x: i32, y: i32) {
+        if !self.is_lit {
+            return;
+        }
+
+        let range: i32 = MAX_LIGHT as i32;
+        let changed_chunks: HashSet<(i32, i32)> = lighting::relight(self, x - range, y - range, x + range, self.height as i32 - 1);
+
+        self.changed_chunks.extend(changed_chunks);
+    }
+
+    pub fn take_changed_chunks(&mut self) -> HashSet<(i32, i32)> {
+        std::mem::take(&mut self.changed_chunks)
+    }
+
+    pub fn loaded_chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn unloaded_chunk_count(&self) -> usize {
+        self.unloaded_chunks.len()
+    }
+
+    // keeps every chunk in the columns from min_block_x to max_block_x and a margin around them loaded and compresses the rest,
+    // whole columns are kept since a block change relights everything below it
+    pub fn update_loaded_chunks(&mut self, min_block_x: i32, max_block_x: i32) {
+        let min_chunk_x: i32 = min_block_x.div_euclid(Chunk::SIZE as i32) - Self::LOADED_CHUNK_MARGIN;
+        let max_chunk_x: i32 = max_block_x.div_euclid(Chunk::SIZE as i32) + Self::LOADED_CHUNK_MARGIN;
+        let is_loaded_column = |chunk_x: i32| chunk_x >= min_chunk_x && chunk_x <= max_chunk_x;
+
+        let unloaded_positions: Vec<(i32, i32)> = self.chunks.keys().copied().filter(|&(chunk_x, _)| !is_loaded_column(chunk_x)).collect();
+        for chunk_position in unloaded_positions {
+            if let Some(chunk) = self.chunks.remove(&chunk_position) {
+                self.unloaded_chunks.insert(chunk_position, chunk.compress());
+            }
+        }
+
+        let chunk_row_count: i32 = self.height.div_ceil(Chunk::SIZE) as i32;
+        let max_chunk_x: i32 = max_chunk_x.min(self.width.div_ceil(Chunk::SIZE) as i32 - 1);
+        for chunk_x in min_chunk_x.max(0)..=max_chunk_x {
+            for chunk_y in 0..chunk_row_count {
+                self.load_chunk((chunk_x, chunk_y));
+            }
+        }
+    }
+
+    fn load_chunk(&mut self, chunk_position: (i32, i32)) {
+        if let Some(compressed_chunk) = self.unloaded_chunks.remove(&chunk_position) {
+            self.chunks.insert(chunk_position, Chunk::decompress(&compressed_chunk));
+        }
+    }
+
+    pub fn save(&self, path: &Path, player: &Player) -> std::io::Result<()> {
+        world_file::save(self, player, path)
+    }
+
+    pub fn load(path: &Path, blocks: Arc<BlockRegistry>) -> std::io::Result<(World, Player)> {
+        world_file::load(path, blocks)
     }
 }
 
-impl Default for World {
-    fn default() -> Self {
-        Self {
-            world_map: world_generator::generate_world(),
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_world() -> World {
+        World::new(64, 48, 1, BlockRegistry::load_default())
+    }
+
+    // one cell past each of the four edges
+    fn outside_cells(world: &World) -> [(i32, i32); 4] {
+        [(-1, 10), (10, -1), (world.width as i32, 10), (10, world.height as i32)]
+    }
+
+    #[test]
+    fn get_block_outside_of_the_map_is_none() {
+        let world: World = test_world();
+
+        for (x, y) in outside_cells(&world) {
+            assert_eq!(world.get_block(x, y), None, "cell {}, {}", x, y);
+        }
+    }
+
+    #[test]
+    fn set_block_outside_of_the_map_changes_nothing() {
+        let mut world: World = test_world();
+        let stone: u16 = world.blocks.id("stone").unwrap();
+
+        for (x, y) in outside_cells(&world) {
+            assert_eq!(world.set_block(x, y, stone), None, "cell {}, {}", x, y);
+        }
+
+        assert_eq!(world.loaded_chunk_count(), 0);
+        assert!(world.take_changed_chunks().is_empty());
+        // the cells just inside of the edges are not written by wrapping coordinates
+        for (x, y) in [(0, 10), (10, 0), (world.width as i32 - 1, 10), (10, world.height as i32 - 1)] {
+            assert_eq!(world.get_block(x, y), Some(AIR));
+        }
+    }
+
+    #[test]
+    fn outside_of_the_map_is_solid() {
+        let world: World = test_world();
+
+        for (x, y) in outside_cells(&world) {
+            assert!(world.is_solid(x, y), "cell {}, {}", x, y);
         }
+        assert!(!world.is_solid(0, 0));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn chunks_outside_of_the_view_are_unloaded_and_keep_their_blocks() {
+        let mut world: World = World::new(Chunk::SIZE * 10, 48, 1, BlockRegistry::load_default());
+        let stone: u16 = world.blocks.id("stone").unwrap();
+        let stone_wall: u16 = world.blocks.id("stone_wall").unwrap();
+        for chunk_x in 0..10 {
+            world.set_block(chunk_x * Chunk::SIZE as i32 + 3, 40, stone);
+        }
+        world.set_wall(290, 41, stone_wall);
+
+        // the view covers chunk column 1, so columns 0 to 3 stay loaded
+        world.update_loaded_chunks(Chunk::SIZE as i32, Chunk::SIZE as i32 * 2 - 1);
+        assert_eq!((world.loaded_chunk_count(), world.unloaded_chunk_count()), (4, 6));
+        assert_eq!(world.get_block(9 * Chunk::SIZE as i32 + 3, 40), Some(stone));
+        assert_eq!(world.get_wall(290, 41), Some(stone_wall));
+
+        // writing to an unloaded chunk loads it
+        world.set_block(9 * Chunk::SIZE as i32 + 4, 40, stone);
+        assert_eq!((world.loaded_chunk_count(), world.unloaded_chunk_count()), (5, 5));
+
+        // moving the view loads the chunks around it again
+        world.update_loaded_chunks(Chunk::SIZE as i32 * 8, Chunk::SIZE as i32 * 9 - 1);
+        assert_eq!((world.loaded_chunk_count(), world.unloaded_chunk_count()), (4, 6));
+        for chunk_x in 0..10 {
+            assert_eq!(world.get_block(chunk_x * Chunk::SIZE as i32 + 3, 40), Some(stone), "chunk column {}", chunk_x);
+        }
+        assert_eq!(world.get_block(9 * Chunk::SIZE as i32 + 4, 40), Some(stone));
+    }
+}
@@ -1,8 +0,0 @@
-use crate::world::block_textures::BlockTypes;
-
-pub const SOLIDS: [u16; 4] = [
-    BlockTypes::Grass as u16,
-    BlockTypes::Dirt as u16,
-    BlockTypes::Stone as u16,
-    BlockTypes::Planks as u16,
-];
\ No newline at end of file
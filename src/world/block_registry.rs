@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+use std::path::Path;
+use serde::Deserialize;
+use crate::world::lighting::MAX_LIGHT;
+
+pub const AIR: u16 = 0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub enum BlockFlag {
+    // other blocks can be placed into this block without breaking it first
+    Replaceable,
+    // can not be mined by the player
+    Unbreakable,
+    // placed in the background wall layer instead of the block layer
+    Wall,
+}
+
+#[derive(Default, Deserialize)]
+enum BlockDrop {
+    #[default]
+    Itself,
+    Nothing,
+    Block(String),
+}
+
+// a block definition as written in the block data file
+#[derive(Deserialize)]
+struct BlockDefinitionFile {
+    id: u16,
+    name: String,
+    texture: String,
+    solid: bool,
+    #[serde(default)]
+    hardness: f32,
+    #[serde(default)]
+    light_emission: u8,
+    #[serde(default = "default_light_attenuation")]
+    light_attenuation: u8,
+    #[serde(default)]
+    tool_speed: Option<f32>,
+    #[serde(default)]
+    drop: BlockDrop,
+    #[serde(default)]
+    flags: Vec<BlockFlag>,
+}
+
+pub struct BlockDefinition {
+    pub id: u16,
+    pub name: String,
+    // file name of the texture in the texture directory, without the extension
+    pub texture: String,
+    pub solid: bool,
+    // seconds needed to mine the block without a tool, 0 mines instantly
+    pub hardness: f32,
+    // mining speed multiplier while this block is the selected item
+    pub tool_speed: f32,
+    // light level given off by the block, up to MAX_LIGHT
+    pub light_emission: u8,
+    // light levels lost when light enters the block, at least 1
+    pub light_attenuation: u8,
+    // id of the block added to the inventory when this block is mined
+    pub drop: Option<u16>,
+    pub flags: Vec<BlockFlag>,
+}
+
+fn default_light_attenuation() -> u8 {
+    1
+}
+
+impl BlockDefinition {
+    pub fn has_flag(&self, flag: BlockFlag) -> bool {
+        self.flags.contains(&flag)
+    }
+}
+
+pub struct BlockRegistry {
+    // indexed by block id, ids without a definition are None
+    definitions: Vec<Option<BlockDefinition>>,
+    ids_by_name: HashMap<String, u16>,
+}
+
+impl BlockRegistry {
+    pub const DEFAULT_PATH: &'static str = "assets/blocks.ron";
+    // blocks referenced by name from code, mostly by the world generator
+    pub const REQUIRED_BLOCKS: [&'static str; 18] = [
+        "air", "grass", "dirt", "stone", "log", "leaves", "planks", "torch", "dirt_wall", "stone_wall", "wood_wall",
+        "sand", "sandstone", "snow", "mud", "jungle_grass", "cactus", "water",
+    ];
+
+    // the block data shipped with the game, shared by the tests
+    #[cfg(test)]
+    pub fn load_default() -> std::sync::Arc<Self> {
+        std::sync::Arc::new(Self::load(Path::new(Self::DEFAULT_PATH)).expect("block data loads"))
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text: String = std::fs::read_to_string(path).map_err(|error| format!("Failed to read {}: {}", path.display(), error))?;
+
+        Self::from_ron(&text).map_err(|error| format!("Invalid block data in {}: {}", path.display(), error))
+    }
+
+    pub fn from_ron(text: &str) -> Result<Self, String> {
+        let definition_files: Vec<BlockDefinitionFile> = ron::from_str(text).map_err(|error| error.to_string())?;
+
+        let mut ids_by_name: HashMap<String, u16> = HashMap::new();
+        for definition_file in definition_files.iter() {
+            if ids_by_name.insert(definition_file.name.clone(), definition_file.id).is_some() {
+                return Err(format!("Duplicate block name {}", definition_file.name));
+            }
+        }
+
+        let mut definitions: Vec<Option<BlockDefinition>> = Vec::new();
+        for definition_file in definition_files {
+            if definition_file.light_emission > MAX_LIGHT {
+                return Err(format!("Light emission of block {} is above {}", definition_file.name, MAX_LIGHT));
+            }
+            if definition_file.light_attenuation == 0 {
+                return Err(format!("Light attenuation of block {} must be at least 1", definition_file.name));
+            }
+
+            let drop: Option<u16> = match definition_file.drop {
+                BlockDrop::Itself => Some(definition_file.id),
+                BlockDrop::Nothing => None,
+                BlockDrop::Block(name) => Some(*ids_by_name.get(&name).ok_or(format!("Unknown drop {} for block {}", name, definition_file.name))?),
+            };
+
+            let index: usize = definition_file.id as usize;
+            if definitions.len() <= index {
+                definitions.resize_with(index + 1, || None);
+            }
+            if definitions[index].is_some() {
+                return Err(format!("Duplicate block id {}", definition_file.id));
+            }
+
+            definitions[index] = Some(BlockDefinition {
+                id: definition_file.id,
+                name: definition_file.name,
+                texture: definition_file.texture,
+                solid: definition_file.solid,
+                hardness: definition_file.hardness,
+                tool_speed: definition_file.tool_speed.unwrap_or(1.0),
+                light_emission: definition_file.light_emission,
+                light_attenuation: definition_file.light_attenuation,
+                drop,
+                flags: definition_file.flags,
+            });
+        }
+
+        if ids_by_name.get("air") != Some(&AIR) {
+            return Err(format!("Block id {} must be air", AIR));
+        }
+        for name in Self::REQUIRED_BLOCKS {
+            if !ids_by_name.contains_key(name) {
+                return Err(format!("Missing required block {}", name));
+            }
+        }
+
+        Ok(Self {
+            definitions,
+            ids_by_name,
+        })
+    }
+
+    pub fn get(&self, id: u16) -> Option<&BlockDefinition> {
+        self.definitions.get(id as usize)?.as_ref()
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = u16> + '_ {
+        self.definitions.iter().flatten().map(|definition| definition.id)
+    }
+
+    pub fn contains(&self, id: u16) -> bool {
+        self.get(id).is_some()
+    }
+
+    pub fn id(&self, name: &str) -> Option<u16> {
+        self.ids_by_name.get(name).copied()
+    }
+
+    pub fn is_wall(&self, id: u16) -> bool {
+        self.get(id).is_some_and(|definition| definition.has_flag(BlockFlag::Wall))
+    }
+
+    // unknown blocks are treated as solid so they can not be walked through
+    pub fn is_solid(&self, id: u16) -> bool {
+        self.get(id).is_none_or(|definition| definition.solid)
+    }
+}
@@ -1,19 +0,0 @@
-pub enum BlockTypes {
-    Air = 0,
-    Grass = 1,
-    Dirt = 2,
-    Stone = 3,
-    Log = 4,
-    Leaves = 5,
-    Planks = 6,
-}
-
-pub const TEXTURE_MAP: [&[[&str; 5]; 5]; 7] = [&AIR, &GRASS, &DIRT, &STONE, &LOG, &LEAVES, &PLANKS];
-
-pub const AIR: [[&str; 5]; 5] = [["#a3e0ef","#a3e0ef","#a3e0ef","#a3e0ef","#a3e0ef"],["#a3e0ef","#a3e0ef","#a3e0ef","#a3e0ef","#a3e0ef"],["#a3e0ef","#a3e0ef","#a3e0ef","#a3e0ef","#a3e0ef"],["#a3e0ef","#a3e0ef","#a3e0ef","#a3e0ef","#a3e0ef"],["#a3e0ef","#a3e0ef","#a3e0ef","#a3e0ef","#a3e0ef"]];
-pub const DIRT: [[&str; 5]; 5] = [["#aa6468","#762e31","#762e31","#762e31","#762e31"],["#762e31","#aa6468","#762e31","#762e31","#aa6468"],["#762e31","#aa6468","#762e31","#aa6468","#762e31"],["#762e31","#762e31","#aa6468","#762e31","#aa6468"],["#aa6468","#762e31","#762e31","#762e31","#762e31"]];
-pub const GRASS: [[&str; 5]; 5] = [["#14a112","#18ff14","#18ff14","#14a112","#18ff14"],["#18ff14","#14a112","#14a112","#14a112","#14a112"],["#14a112","#aa6468","#14a112","#aa6468","#14a112"],["#762e31","#762e31","#aa6468","#762e31","#aa6468"],["#aa6468","#762e31","#762e31","#762e31","#762e31"]];
-pub const STONE: [[&str; 5]; 5] = [["#585562","#585562","#8f8f8f","#585562","#585562"],["#585562","#8f8f8f","#585562","#8f8f8f","#585562"],["#8f8f8f","#585562","#585562","#8f8f8f","#8f8f8f"],["#585562","#8f8f8f","#8f8f8f","#585562","#8f8f8f"],["#585562","#585562","#8f8f8f","#585562","#585562"]];
-pub const LOG: [[&str; 5] ; 5] = [["#473333","#693f3f","#693f3f","#8d6262","#693f3f"],["#473333","#473333","#693f3f","#8d6262","#8d6262"],["#693f3f","#473333","#8d6262","#693f3f","#8d6262"],["#473333","#693f3f","#693f3f","#8d6262","#693f3f"],["#473333","#473333","#693f3f","#8d6262","#8d6262"]];
-pub const LEAVES: [[&str; 5] ; 5] = [["#72b43c","#78e71d","#72b43c","#4f8523","#78e71d"],["#4f8523","#72b43c","#4f8523","#78e71d","#72b43c"],["#72b43c","#78e71d","#72b43c","#4f8523","#78e71d"],["#78e71d","#72b43c","#4f8523","#72b43c","#4f8523"],["#4f8523","#4f8523","#78e71d","#4f8523","#72b43c"]];
-pub const PLANKS: [[&str; 5] ; 5] = [["#918012","#ab9717","#ab9717","#918012","#ab9717"],["#d6be1f","#cdb40e","#d6be1f","#d6be1f","#cdb40e"],["#ab9717","#918012","#ab9717","#918012","#918012"],["#cdb40e","#d6be1f","#d6be1f","#cdb40e","#cdb40e"],["#918012","#ab9717","#918012","#ab9717","#918012"]];
\ No newline at end of file
@@ -0,0 +1,87 @@
+use std::io::{Read, Write};
+use flate2::Compression;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use crate::world::block_registry::AIR;
+use crate::world::lighting::MAX_LIGHT;
+
+pub struct Chunk {
+    pub blocks: Box<[u16; Chunk::AREA]>,
+    // background walls behind the blocks, AIR where there is no wall
+    pub walls: Box<[u16; Chunk::AREA]>,
+    // light level of every cell, see lighting, a chunk that is not allocated is fully lit
+    pub light: Box<[u8; Chunk::AREA]>,
+}
+
+impl Chunk {
+    pub const SIZE: u32 = 32;
+    pub const AREA: usize = (Self::SIZE * Self::SIZE) as usize;
+
+    pub fn get_block(&self, local_x: u32, local_y: u32) -> u16 {
+        self.blocks[(local_x + local_y * Self::SIZE) as usize]
+    }
+
+    pub fn set_block(&mut self, local_x: u32, local_y: u32, block_type: u16) {
+        self.blocks[(local_x + local_y * Self::SIZE) as usize] = block_type;
+    }
+
+    pub fn get_wall(&self, local_x: u32, local_y: u32) -> u16 {
+        self.walls[(local_x + local_y * Self::SIZE) as usize]
+    }
+
+    pub fn set_wall(&mut self, local_x: u32, local_y: u32, wall_type: u16) {
+        self.walls[(local_x + local_y * Self::SIZE) as usize] = wall_type;
+    }
+
+    pub fn get_light(&self, local_x: u32, local_y: u32) -> u8 {
+        self.light[(local_x + local_y * Self::SIZE) as usize]
+    }
+
+    pub fn set_light(&mut self, local_x: u32, local_y: u32, level: u8) {
+        self.light[(local_x + local_y * Self::SIZE) as usize] = level;
+    }
+
+    // a fully lit chunk containing only air does not need to be kept in memory
+    pub fn is_empty(&self) -> bool {
+        self.blocks.iter().chain(self.walls.iter()).all(|&block_type| block_type == AIR) && self.light.iter().all(|&level| level == MAX_LIGHT)
+    }
+
+    // blocks then walls as little endian u16, the layout used by world files
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.blocks.iter().chain(self.walls.iter()).flat_map(|block_type| block_type.to_le_bytes()).collect()
+    }
+
+    // kept while the chunk is unloaded, a mostly uniform chunk compresses to a few dozen bytes,
+    // unlike in world files the light is kept so the chunk does not need to be relit when it is loaded again
+    pub fn compress(&self) -> Vec<u8> {
+        let mut encoder: ZlibEncoder<Vec<u8>> = ZlibEncoder::new(Vec::new(), Compression::fast());
+        encoder.write_all(&self.to_bytes()).expect("writing to memory does not fail");
+        encoder.write_all(self.light.as_slice()).expect("writing to memory does not fail");
+
+        encoder.finish().expect("writing to memory does not fail")
+    }
+
+    pub fn decompress(compressed_bytes: &[u8]) -> Self {
+        let mut bytes: Vec<u8> = Vec::with_capacity(Self::AREA * 5);
+        ZlibDecoder::new(compressed_bytes).read_to_end(&mut bytes).expect("compressed chunks are only created by Chunk::compress");
+
+        let (block_bytes, light_bytes) = bytes.split_at(Self::AREA * 4);
+        let mut chunk: Chunk = Chunk::default();
+        for (block_type, bytes) in chunk.blocks.iter_mut().chain(chunk.walls.iter_mut()).zip(block_bytes.chunks_exact(2)) {
+            *block_type = u16::from_le_bytes([bytes[0], bytes[1]]);
+        }
+        chunk.light.copy_from_slice(light_bytes);
+
+        chunk
+    }
+}
+
+impl Default for Chunk {
+    fn default() -> Self {
+        Self {
+            blocks: Box::new([AIR; Self::AREA]),
+            walls: Box::new([AIR; Self::AREA]),
+            light: Box::new([MAX_LIGHT; Self::AREA]),
+        }
+    }
+}
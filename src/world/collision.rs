@@ -0,0 +1,319 @@
+/*
+    swept axis aligned bounding box collision against the block grid
+
+    boxes are in pixel coordinates with the origin at the top left, movement is resolved one axis at a time
+    and every block column or row the leading edge passes through is checked, so fast movement can not skip
+    through thin floors or walls
+*/
+use crate::renderer::Renderer;
+use crate::world::World;
+
+// distance in blocks under which an edge counts as resting against a block boundary
+const CONTACT_EPSILON: f32 = 0.001;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Contacts {
+    pub on_ground: bool,
+    pub touching_ceiling: bool,
+    pub touching_left_wall: bool,
+    pub touching_right_wall: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CollisionResult {
+    pub x: f32,
+    pub y: f32,
+    // movement on the axis was stopped by a solid block
+    pub collided_x: bool,
+    pub collided_y: bool,
+    // height in pixels the box was lifted to step onto a ledge, 0.0 if it did not step
+    pub step_height: f32,
+    // contacts at the resolved position
+    pub contacts: Contacts,
+}
+
+// moves the box by the given displacement, horizontal first, stopping at the first solid block on each axis,
+// a grounded box blocked horizontally is lifted onto ledges up to max_step_height pixels high if there is room above it
+pub fn move_and_collide(world: &World, aabb: &Aabb, delta_x: f32, delta_y: f32, max_step_height: f32) -> CollisionResult {
+    let (mut x, mut collided_x) = sweep_x(world, aabb, delta_x);
+    let mut moved_aabb: Aabb = Aabb { x, ..*aabb };
+    let mut step_height: f32 = 0.0;
+
+    if collided_x && max_step_height > 0.0 && get_contacts(world, aabb).on_ground {
+        let (lifted_y, hit_ceiling) = sweep_y(world, aabb, -max_step_height);
+
+        if !hit_ceiling {
+            let lifted_aabb: Aabb = Aabb { y: lifted_y, ..*aabb };
+            let (stepped_x, stepped_collided_x) = sweep_x(world, &lifted_aabb, delta_x);
+
+            // only step if it gets the box further than staying on the ground does
+            if (stepped_x - aabb.x).abs() > (x - aabb.x).abs() {
+                let (landed_y, _) = sweep_y(world, &Aabb { x: stepped_x, ..lifted_aabb }, max_step_height);
+
+                x = stepped_x;
+                collided_x = stepped_collided_x;
+                step_height = aabb.y - landed_y;
+                moved_aabb = Aabb { x, y: landed_y, ..*aabb };
+            }
+        }
+    }
+
+    let (y, collided_y) = sweep_y(world, &moved_aabb, delta_y);
+    let resolved_aabb: Aabb = Aabb { y, ..moved_aabb };
+
+    CollisionResult {
+        x,
+        y,
+        collided_x,
+        collided_y,
+        step_height,
+        contacts: get_contacts(world, &resolved_aabb),
+    }
+}
+
+pub fn get_contacts(world: &World, aabb: &Aabb) -> Contacts {
+    let (left_column, right_column) = overlapped_blocks(aabb.x, aabb.width);
+    let (top_row, bottom_row) = overlapped_blocks(aabb.y, aabb.height);
+
+    let row_is_solid = |row: i32| (left_column..=right_column).any(|column| world.is_solid(column, row));
+    let column_is_solid = |column: i32| (top_row..=bottom_row).any(|row| world.is_solid(column, row));
+
+    Contacts {
+        on_ground: aligned_block_edge(aabb.y + aabb.height).is_some_and(row_is_solid),
+        touching_ceiling: aligned_block_edge(aabb.y).is_some_and(|edge| row_is_solid(edge - 1)),
+        touching_left_wall: aligned_block_edge(aabb.x).is_some_and(|edge| column_is_solid(edge - 1)),
+        touching_right_wall: aligned_block_edge(aabb.x + aabb.width).is_some_and(column_is_solid),
+    }
+}
+
+fn sweep_x(world: &World, aabb: &Aabb, delta_x: f32) -> (f32, bool) {
+    let (top_row, bottom_row) = overlapped_blocks(aabb.y, aabb.height);
+    let column_is_solid = |column: i32| (top_row..=bottom_row).any(|row| world.is_solid(column, row));
+
+    if delta_x > 0.0 {
+        // columns already overlapped by the box are skipped so a block placed inside it does not push it back
+        let first_column: i32 = ((aabb.x + aabb.width) / Renderer::BLOCK_PIXEL_COUNT - CONTACT_EPSILON).ceil() as i32;
+        let last_column: i32 = ((aabb.x + aabb.width + delta_x) / Renderer::BLOCK_PIXEL_COUNT).ceil() as i32 - 1;
+
+        for column in first_column..=last_column {
+            if column_is_solid(column) {
+                return (column as f32 * Renderer::BLOCK_PIXEL_COUNT - aabb.width, true);
+            }
+        }
+    } else if delta_x < 0.0 {
+        let first_column: i32 = (aabb.x / Renderer::BLOCK_PIXEL_COUNT + CONTACT_EPSILON).floor() as i32 - 1;
+        let last_column: i32 = ((aabb.x + delta_x) / Renderer::BLOCK_PIXEL_COUNT).floor() as i32;
+
+        for column in (last_column..=first_column).rev() {
+            if column_is_solid(column) {
+                return ((column + 1) as f32 * Renderer::BLOCK_PIXEL_COUNT, true);
+            }
+        }
+    }
+
+    (aabb.x + delta_x, false)
+}
+
+fn sweep_y(world: &World, aabb: &Aabb, delta_y: f32) -> (f32, bool) {
+    let (left_column, right_column) = overlapped_blocks(aabb.x, aabb.width);
+    let row_is_solid = |row: i32| (left_column..=right_column).any(|column| world.is_solid(column, row));
+
+    if delta_y > 0.0 {
+        let first_row: i32 = ((aabb.y + aabb.height) / Renderer::BLOCK_PIXEL_COUNT - CONTACT_EPSILON).ceil() as i32;
+        let last_row: i32 = ((aabb.y + aabb.height + delta_y) / Renderer::BLOCK_PIXEL_COUNT).ceil() as i32 - 1;
+
+        for row in first_row..=last_row {
+            if row_is_solid(row) {
+                return (row as f32 * Renderer::BLOCK_PIXEL_COUNT - aabb.height, true);
+            }
+        }
+    } else if delta_y < 0.0 {
+        let first_row: i32 = (aabb.y / Renderer::BLOCK_PIXEL_COUNT + CONTACT_EPSILON).floor() as i32 - 1;
+        let last_row: i32 = ((aabb.y + delta_y) / Renderer::BLOCK_PIXEL_COUNT).floor() as i32;
+
+        for row in (last_row..=first_row).rev() {
+            if row_is_solid(row) {
+                return ((row + 1) as f32 * Renderer::BLOCK_PIXEL_COUNT, true);
+            }
+        }
+    }
+
+    (aabb.y + delta_y, false)
+}
+
+// first and last block index covered by a span in pixels, an end resting exactly on a block boundary does not cover the next block
+fn overlapped_blocks(start: f32, length: f32) -> (i32, i32) {
+    (
+        (start / Renderer::BLOCK_PIXEL_COUNT + CONTACT_EPSILON).floor() as i32,
+        ((start + length) / Renderer::BLOCK_PIXEL_COUNT - CONTACT_EPSILON).ceil() as i32 - 1,
+    )
+}
+
+// index of the block boundary the edge rests on, if any
+fn aligned_block_edge(edge: f32) -> Option<i32> {
+    let block_edge: f32 = (edge / Renderer::BLOCK_PIXEL_COUNT).round();
+
+    if (edge / Renderer::BLOCK_PIXEL_COUNT - block_edge).abs() < CONTACT_EPSILON {
+        Some(block_edge as i32)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::block_registry::BlockRegistry;
+
+    const BLOCK: f32 = Renderer::BLOCK_PIXEL_COUNT;
+    const FLOOR_ROW: i32 = 20;
+
+    // an empty map with a one block thick floor
+    fn test_world() -> World {
+        let mut world: World = World::new(32, 32, 1, BlockRegistry::load_default());
+        let stone: u16 = world.blocks.id("stone").unwrap();
+
+        for x in 0..world.width as i32 {
+            world.set_block(x, FLOOR_ROW, stone);
+        }
+
+        world
+    }
+
+    fn player_box(x: f32, y: f32) -> Aabb {
+        Aabb { x, y, width: 40.0, height: 86.0 }
+    }
+
+    #[test]
+    fn fast_fall_does_not_tunnel_through_a_thin_floor() {
+        let world: World = test_world();
+
+        // many blocks per tick, far past the floor
+        for delta_y in [BLOCK * 20.0, BLOCK * 25.0, BLOCK * 100.0] {
+            let result: CollisionResult = move_and_collide(&world, &player_box(300.0, 0.0), 0.0, delta_y, 0.0);
+
+            assert_eq!(result.y, FLOOR_ROW as f32 * BLOCK - 86.0, "delta {}", delta_y);
+            assert!(result.collided_y);
+            assert!(result.contacts.on_ground);
+        }
+    }
+
+    #[test]
+    fn map_edges_stop_the_box() {
+        let world: World = test_world();
+        let right_edge_x: f32 = world.width as f32 * BLOCK - 40.0;
+
+        let left: CollisionResult = move_and_collide(&world, &player_box(0.0, 300.0), -100.0, 0.0, 0.0);
+        assert_eq!(left.x, 0.0);
+        assert!(left.collided_x);
+        assert!(left.contacts.touching_left_wall);
+
+        let right: CollisionResult = move_and_collide(&world, &player_box(right_edge_x, 300.0), 100.0, 0.0, 0.0);
+        assert_eq!(right.x, right_edge_x);
+        assert!(right.collided_x);
+        assert!(right.contacts.touching_right_wall);
+
+        let top: CollisionResult = move_and_collide(&world, &player_box(300.0, 0.0), 0.0, -100.0, 0.0);
+        assert_eq!(top.y, 0.0);
+        assert!(top.collided_y);
+        assert!(top.contacts.touching_ceiling);
+    }
+
+    #[test]
+    fn box_resting_on_a_block_boundary() {
+        let mut world: World = test_world();
+        let stone: u16 = world.blocks.id("stone").unwrap();
+        let resting_y: f32 = FLOOR_ROW as f32 * BLOCK - 86.0;
+
+        // standing in the open, exactly on the floor
+        let standing: Contacts = get_contacts(&world, &player_box(300.0, resting_y));
+        assert_eq!(standing, Contacts { on_ground: true, ..Contacts::default() });
+
+        // a wall right of the box, the right edge lies exactly on its boundary
+        for row in FLOOR_ROW - 4..FLOOR_ROW {
+            world.set_block(15, row, stone);
+        }
+        let against_wall: Aabb = player_box(15.0 * BLOCK - 40.0, resting_y);
+        assert_eq!(get_contacts(&world, &against_wall), Contacts { on_ground: true, touching_right_wall: true, ..Contacts::default() });
+
+        // moving into the wall does not move the box, moving away from it and along the floor is free
+        let pushed: CollisionResult = move_and_collide(&world, &against_wall, 10.0, 1.0, 0.0);
+        assert_eq!((pushed.x, pushed.y), (against_wall.x, resting_y));
+        assert!(pushed.collided_x && pushed.collided_y);
+
+        let released: CollisionResult = move_and_collide(&world, &against_wall, -10.0, 0.0, 0.0);
+        assert_eq!(released.x, against_wall.x - 10.0);
+        assert!(!released.collided_x);
+        assert_eq!(released.contacts, Contacts { on_ground: true, ..Contacts::default() });
+    }
+
+    // a box standing on the floor with its right edge against column 15, with the given blocks in that column
+    fn step_world(ledge_rows: &[i32], ceiling_row: Option<i32>) -> (World, Aabb) {
+        let mut world: World = test_world();
+        let stone: u16 = world.blocks.id("stone").unwrap();
+
+        for &row in ledge_rows {
+            world.set_block(15, row, stone);
+        }
+        if let Some(row) = ceiling_row {
+            for column in 13..15 {
+                world.set_block(column, row, stone);
+            }
+        }
+
+        (world, player_box(15.0 * BLOCK - 40.0, FLOOR_ROW as f32 * BLOCK - 86.0))
+    }
+
+    #[test]
+    fn steps_onto_a_one_block_ledge() {
+        let (world, standing) = step_world(&[FLOOR_ROW - 1], None);
+
+        let result: CollisionResult = move_and_collide(&world, &standing, 10.0, 1.0, BLOCK);
+        assert_eq!(result.step_height, BLOCK);
+        assert_eq!((result.x, result.y), (standing.x + 10.0, standing.y - BLOCK));
+        assert!(!result.collided_x);
+        assert!(result.contacts.on_ground);
+    }
+
+    #[test]
+    fn does_not_step_onto_a_two_block_wall() {
+        let (world, standing) = step_world(&[FLOOR_ROW - 2, FLOOR_ROW - 1], None);
+
+        let result: CollisionResult = move_and_collide(&world, &standing, 10.0, 1.0, BLOCK);
+        assert_eq!(result.step_height, 0.0);
+        assert_eq!((result.x, result.y), (standing.x, standing.y));
+        assert!(result.collided_x);
+    }
+
+    #[test]
+    fn does_not_step_when_the_ceiling_blocks_the_lift() {
+        // the ceiling is less than a block above the top of the box
+        let (world, standing) = step_world(&[FLOOR_ROW - 1], Some(16));
+        assert!(standing.y - 17.0 * BLOCK < BLOCK);
+
+        let result: CollisionResult = move_and_collide(&world, &standing, 10.0, 1.0, BLOCK);
+        assert_eq!(result.step_height, 0.0);
+        assert_eq!((result.x, result.y), (standing.x, standing.y));
+        assert!(result.collided_x);
+    }
+
+    #[test]
+    fn does_not_step_while_airborne() {
+        let (world, standing) = step_world(&[FLOOR_ROW - 1], None);
+        let falling: Aabb = Aabb { y: standing.y - 10.0, ..standing };
+
+        let result: CollisionResult = move_and_collide(&world, &falling, 10.0, 0.0, BLOCK);
+        assert_eq!(result.step_height, 0.0);
+        assert_eq!((result.x, result.y), (falling.x, falling.y));
+        assert!(result.collided_x);
+        assert!(!result.contacts.on_ground);
+    }
+}
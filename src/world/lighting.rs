@@ -0,0 +1,197 @@
+use std::collections::HashSet;
+use crate::world::World;
+use crate::world::block_registry::AIR;
+
+pub const MAX_LIGHT: u8 = 15;
+
+// lights every cell of the map, sunlight and block light are combined into one level stored in the chunks
+pub fn calculate(world: &mut World) {
+    let (max_x, max_y) = (world.width as i32 - 1, world.height as i32 - 1);
+    propagate(world, 0, 0, max_x, max_y);
+}
+
+// recalculates the light of every cell inside the inclusive area and returns the chunks with changed light
+// cells outside of the area keep their level and light the area from its border, so the area has to contain
+// every cell that can be affected by a change, see World::set_block
+pub fn relight(world: &mut World, min_x: i32, min_y: i32, max_x: i32, max_y: i32) -> HashSet<(i32, i32)> {
+    let (min_x, min_y) = (min_x.max(0), min_y.max(0));
+    let (max_x, max_y) = (max_x.min(world.width as i32 - 1), max_y.min(world.height as i32 - 1));
+
+    let mut changed_chunks: HashSet<(i32, i32)> = HashSet::new();
+    if min_x > max_x || min_y > max_y {
+        return changed_chunks;
+    }
+
+    let previous_levels: Vec<u8> = (min_y..=max_y)
+        .flat_map(|y| (min_x..=max_x).map(move |x| (x, y)))
+        .map(|(x, y)| world.read_light(x, y))
+        .collect();
+
+    propagate(world, min_x, min_y, max_x, max_y);
+
+    let area_width: usize = (max_x - min_x + 1) as usize;
+    for (area_index, previous_level) in previous_levels.into_iter().enumerate() {
+        let (x, y) = (min_x + (area_index % area_width) as i32, min_y + (area_index / area_width) as i32);
+
+        if world.read_light(x, y) != previous_level {
+            changed_chunks.insert(World::chunk_position(x, y));
+        }
+    }
+
+    changed_chunks
+}
+
+// the area must already be clamped to the map
+fn propagate(world: &mut World, min_x: i32, min_y: i32, max_x: i32, max_y: i32) {
+    // cells are processed from the brightest level down so every cell is final the first time it is spread from
+    let mut queues: Vec<Vec<(i32, i32)>> = vec![Vec::new(); MAX_LIGHT as usize + 1];
+
+    for x in min_x..=max_x {
+        let mut sunlight: u8 = MAX_LIGHT;
+
+        for y in 0..=max_y {
+            // sunlight travels straight down without loss through blocks that only attenuate by 1, like air,
+            // walls keep it out so it only spreads into them like any other light
+            let has_wall: bool = world.get_wall(x, y).is_some_and(|wall_type| wall_type != AIR);
+            sunlight = sunlight.saturating_sub(if has_wall { attenuation(world, x, y) } else { attenuation(world, x, y) - 1 });
+
+            if y < min_y {
+                continue;
+            }
+
+            let mut level: u8 = sunlight.max(emission(world, x, y));
+            for (neighbor_x, neighbor_y) in neighbors(x, y) {
+                let is_inside: bool = neighbor_x >= min_x && neighbor_x <= max_x && neighbor_y >= min_y && neighbor_y <= max_y;
+
+                if !is_inside && world.is_in_bounds(neighbor_x, neighbor_y) {
+                    level = level.max(world.read_light(neighbor_x, neighbor_y).saturating_sub(attenuation(world, x, y)));
+                }
+            }
+
+            world.write_light(x, y, level);
+            queues[level as usize].push((x, y));
+        }
+    }
+
+    for level in (1..=MAX_LIGHT).rev() {
+        while let Some((x, y)) = queues[level as usize].pop() {
+            // a cell is queued again when it gets brighter, the old entry is skipped
+            if world.read_light(x, y) != level {
+                continue;
+            }
+
+            for (neighbor_x, neighbor_y) in neighbors(x, y) {
+                if neighbor_x < min_x || neighbor_x > max_x || neighbor_y < min_y || neighbor_y > max_y {
+                    continue;
+                }
+
+                let neighbor_level: u8 = level.saturating_sub(attenuation(world, neighbor_x, neighbor_y));
+
+                if neighbor_level > world.read_light(neighbor_x, neighbor_y) {
+                    world.write_light(neighbor_x, neighbor_y, neighbor_level);
+                    queues[neighbor_level as usize].push((neighbor_x, neighbor_y));
+                }
+            }
+        }
+    }
+}
+
+fn neighbors(x: i32, y: i32) -> [(i32, i32); 4] {
+    [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)]
+}
+
+fn attenuation(world: &World, x: i32, y: i32) -> u8 {
+    world.get_block(x, y)
+        .and_then(|block_type| world.blocks.get(block_type))
+        .map_or(MAX_LIGHT, |block_definition| block_definition.light_attenuation)
+}
+
+fn emission(world: &World, x: i32, y: i32) -> u8 {
+    world.get_block(x, y)
+        .and_then(|block_type| world.blocks.get(block_type))
+        .map_or(0, |block_definition| block_definition.light_emission)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::world::World;
+    use crate::world::block_registry::{BlockRegistry, AIR};
+
+    const WIDTH: u32 = 96;
+    const HEIGHT: u32 = 80;
+    const GROUND_Y: i32 = 30;
+
+    // stone below the ground with a walled cave in it, lit once complete
+    fn test_world() -> World {
+        let mut world: World = World::new(WIDTH, HEIGHT, 1, BlockRegistry::load_default());
+        let stone: u16 = world.blocks.id("stone").unwrap();
+        let stone_wall: u16 = world.blocks.id("stone_wall").unwrap();
+
+        for x in 0..WIDTH as i32 {
+            for y in GROUND_Y..HEIGHT as i32 {
+                world.set_block(x, y, stone);
+                world.set_wall(x, y, stone_wall);
+            }
+        }
+        for x in 20..60 {
+            for y in 45..55 {
+                world.set_block(x, y, AIR);
+            }
+        }
+
+        world.calculate_lighting();
+        world
+    }
+
+    // copies the blocks into a new world and lights it from scratch
+    fn assert_matches_full_lighting(world: &World) {
+        let mut fully_lit_world: World = World::new(world.width, world.height, world.seed, world.blocks.clone());
+        for x in 0..WIDTH as i32 {
+            for y in 0..HEIGHT as i32 {
+                fully_lit_world.set_block(x, y, world.get_block(x, y).unwrap());
+                fully_lit_world.set_wall(x, y, world.get_wall(x, y).unwrap());
+            }
+        }
+        fully_lit_world.calculate_lighting();
+
+        for x in 0..WIDTH as i32 {
+            for y in 0..HEIGHT as i32 {
+                assert_eq!(world.get_light(x, y), fully_lit_world.get_light(x, y), "light at {}, {}", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn placing_and_removing_a_torch_matches_full_lighting() {
+        let mut world: World = test_world();
+        let torch: u16 = world.blocks.id("torch").unwrap();
+
+        world.set_block(40, 54, torch);
+        assert_matches_full_lighting(&world);
+        assert!(world.get_light(40, 54) > 0);
+
+        world.set_block(40, 54, AIR);
+        assert_matches_full_lighting(&world);
+        assert_eq!(world.get_light(40, 50), 0);
+    }
+
+    #[test]
+    fn placing_and_removing_a_roof_matches_full_lighting() {
+        let mut world: World = test_world();
+        let stone: u16 = world.blocks.id("stone").unwrap();
+
+        // a shaft from the surface into the cave lets sunlight in
+        for y in GROUND_Y..45 {
+            world.set_block(30, y, AIR);
+            world.set_wall(30, y, AIR);
+        }
+        assert_matches_full_lighting(&world);
+
+        // a roof over the shaft cuts the sunlight off again
+        world.set_block(30, GROUND_Y, stone);
+        assert_matches_full_lighting(&world);
+
+        world.set_block(30, GROUND_Y, AIR);
+        assert_matches_full_lighting(&world);
+    }
+}
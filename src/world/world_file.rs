@@ -0,0 +1,278 @@
+/*
+    all values are little endian
+
+    header (uncompressed):
+        magic           [u8; 4]
+        version         u16
+        width           u32
+        height          u32
+        seed            u64, [u8; 32] in version 1
+
+    body (zlib compressed):
+        player          position_x, velocity_x, position_y, velocity_y as f32
+        inventory       selected slot u32, slot count u32, slots as block id u16 and count u32, count 0 is an empty slot,
+                        since version 3
+        chunk count     u32
+        chunks          chunk_x i32, chunk_y i32, Chunk::AREA blocks as u16, Chunk::AREA walls as u16 since version 4
+
+    older versions are still loaded, the missing parts start empty
+*/
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Error, ErrorKind, Read, Write};
+use std::path::Path;
+use std::sync::Arc;
+use flate2::Compression;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use crate::player::Player;
+use crate::player::inventory::{Inventory, ItemStack};
+use crate::world::World;
+use crate::world::chunk::Chunk;
+use crate::world::block_registry::{BlockRegistry, AIR};
+
+const MAGIC: [u8; 4] = *b"TRWF";
+const VERSION: u16 = 4;
+const FIRST_VERSION_WITH_INVENTORY: u16 = 3;
+const FIRST_VERSION_WITH_WALLS: u16 = 4;
+
+// writes next to the target first and replaces it once complete, so a crash while saving never leaves a broken save
+pub fn save(world: &World, player: &Player, path: &Path) -> std::io::Result<()> {
+    let mut temporary_path: std::ffi::OsString = path.as_os_str().to_owned();
+    temporary_path.push(".tmp");
+    let temporary_path: &Path = Path::new(&temporary_path);
+
+    let result: std::io::Result<()> = write(world, player, temporary_path).and_then(|_| std::fs::rename(temporary_path, path));
+    if result.is_err() {
+        let _ = std::fs::remove_file(temporary_path);
+    }
+
+    result
+}
+
+fn write(world: &World, player: &Player, path: &Path) -> std::io::Result<()> {
+    let mut writer: BufWriter<File> = BufWriter::new(File::create(path)?);
+
+    writer.write_all(&MAGIC)?;
+    writer.write_all(&VERSION.to_le_bytes())?;
+    writer.write_all(&world.width.to_le_bytes())?;
+    writer.write_all(&world.height.to_le_bytes())?;
+    writer.write_all(&world.seed.to_le_bytes())?;
+
+    let mut encoder: ZlibEncoder<BufWriter<File>> = ZlibEncoder::new(writer, Compression::default());
+
+    for value in [player.position_x, player.velocity_x, player.position_y, player.velocity_y] {
+        encoder.write_all(&value.to_le_bytes())?;
+    }
+
+    encoder.write_all(&(player.inventory.selected_slot as u32).to_le_bytes())?;
+    encoder.write_all(&(player.inventory.slots.len() as u32).to_le_bytes())?;
+    for slot in player.inventory.slots.iter() {
+        let item_stack: ItemStack = slot.unwrap_or(ItemStack { block_id: AIR, count: 0 });
+        encoder.write_all(&item_stack.block_id.to_le_bytes())?;
+        encoder.write_all(&item_stack.count.to_le_bytes())?;
+    }
+
+    encoder.write_all(&((world.chunks.len() + world.unloaded_chunks.len()) as u32).to_le_bytes())?;
+    for (&(chunk_x, chunk_y), chunk) in world.chunks.iter() {
+        encoder.write_all(&chunk_x.to_le_bytes())?;
+        encoder.write_all(&chunk_y.to_le_bytes())?;
+        encoder.write_all(&chunk.to_bytes())?;
+    }
+    for (&(chunk_x, chunk_y), compressed_chunk) in world.unloaded_chunks.iter() {
+        encoder.write_all(&chunk_x.to_le_bytes())?;
+        encoder.write_all(&chunk_y.to_le_bytes())?;
+        encoder.write_all(&Chunk::decompress(compressed_chunk).to_bytes())?;
+    }
+
+    let file: File = encoder.finish()?.into_inner().map_err(|error| error.into_error())?;
+    file.sync_all()
+}
+
+pub fn load(path: &Path, blocks: Arc<BlockRegistry>) -> std::io::Result<(World, Player)> {
+    let mut reader: BufReader<File> = BufReader::new(File::open(path)?);
+
+    if read_bytes::<4>(&mut reader)? != MAGIC {
+        return Err(Error::new(ErrorKind::InvalidData, "not a world file"));
+    }
+
+    let version: u16 = u16::from_le_bytes(read_bytes(&mut reader)?);
+    if version == 0 || version > VERSION {
+        return Err(Error::new(ErrorKind::InvalidData, format!("unsupported world file version {}", version)));
+    }
+
+    let width: u32 = u32::from_le_bytes(read_bytes(&mut reader)?);
+    let height: u32 = u32::from_le_bytes(read_bytes(&mut reader)?);
+    if width > World::MAX_MAP_WIDTH || height > World::MAX_MAP_HEIGHT {
+        return Err(Error::new(ErrorKind::InvalidData, format!("map size {}x{} is too large", width, height)));
+    }
+
+    let seed: u64 = if version == 1 {
+        // version 1 seeds were 32 random bytes, the first 8 are kept
+        let seed_bytes: [u8; 32] = read_bytes(&mut reader)?;
+        u64::from_le_bytes(seed_bytes[..8].try_into().expect("slice has 8 bytes"))
+    } else {
+        u64::from_le_bytes(read_bytes(&mut reader)?)
+    };
+
+    let mut world: World = World::new(width, height, seed, blocks);
+    let mut decoder: ZlibDecoder<BufReader<File>> = ZlibDecoder::new(reader);
+
+    let mut player: Player = Player::new(&world);
+    player.position_x = f32::from_le_bytes(read_bytes(&mut decoder)?);
+    player.velocity_x = f32::from_le_bytes(read_bytes(&mut decoder)?);
+    player.position_y = f32::from_le_bytes(read_bytes(&mut decoder)?);
+    player.velocity_y = f32::from_le_bytes(read_bytes(&mut decoder)?);
+    // nothing to interpolate from on the first frame
+    player.previous_position_x = player.position_x;
+    player.previous_position_y = player.position_y;
+
+    // older saves start with an empty inventory
+    if version >= FIRST_VERSION_WITH_INVENTORY {
+        let selected_slot: u32 = u32::from_le_bytes(read_bytes(&mut decoder)?);
+        let slot_count: u32 = u32::from_le_bytes(read_bytes(&mut decoder)?);
+        if selected_slot as usize >= Inventory::HOTBAR_SIZE || slot_count as usize != Inventory::SLOT_COUNT {
+            return Err(Error::new(ErrorKind::InvalidData, "invalid inventory"));
+        }
+
+        player.inventory.selected_slot = selected_slot as usize;
+        for slot in player.inventory.slots.iter_mut() {
+            let block_id: u16 = u16::from_le_bytes(read_bytes(&mut decoder)?);
+            let count: u32 = u32::from_le_bytes(read_bytes(&mut decoder)?);
+
+            if count > 0 {
+                if !world.blocks.contains(block_id) {
+                    return Err(Error::new(ErrorKind::InvalidData, format!("unknown item id {}", block_id)));
+                }
+                if count > Inventory::MAX_STACK_SIZE {
+                    return Err(Error::new(ErrorKind::InvalidData, format!("stack of {} items is larger than {}", count, Inventory::MAX_STACK_SIZE)));
+                }
+                *slot = Some(ItemStack { block_id, count });
+            }
+        }
+    }
+
+    let chunk_count: u32 = u32::from_le_bytes(read_bytes(&mut decoder)?);
+    for _ in 0..chunk_count {
+        let chunk_x: i32 = i32::from_le_bytes(read_bytes(&mut decoder)?);
+        let chunk_y: i32 = i32::from_le_bytes(read_bytes(&mut decoder)?);
+
+        // older saves have no walls, the wall layer stays air
+        let mut chunk: Chunk = Chunk::default();
+        for (layer, is_wall_layer) in [(&mut chunk.blocks, false), (&mut chunk.walls, true)] {
+            if is_wall_layer && version < FIRST_VERSION_WITH_WALLS {
+                continue;
+            }
+
+            let block_bytes: [u8; Chunk::AREA * 2] = read_bytes(&mut decoder)?;
+
+            for (block_type, bytes) in layer.iter_mut().zip(block_bytes.chunks_exact(2)) {
+                *block_type = u16::from_le_bytes([bytes[0], bytes[1]]);
+
+                if !world.blocks.contains(*block_type) {
+                    return Err(Error::new(ErrorKind::InvalidData, format!("unknown block id {}", block_type)));
+                }
+                if *block_type != AIR && world.blocks.is_wall(*block_type) != is_wall_layer {
+                    return Err(Error::new(ErrorKind::InvalidData, format!("block id {} in the wrong layer", block_type)));
+                }
+            }
+        }
+
+        world.chunks.insert((chunk_x, chunk_y), chunk);
+    }
+
+    world.calculate_lighting();
+
+    Ok((world, player))
+}
+
+fn read_bytes<const N: usize>(reader: &mut impl Read) -> std::io::Result<[u8; N]> {
+    let mut bytes: [u8; N] = [0; N];
+    reader.read_exact(&mut bytes)?;
+
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temporary_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("game1_{}_{}.trw", name, std::process::id()))
+    }
+
+    #[test]
+    fn save_replaces_the_file_and_loads_back() {
+        let path: std::path::PathBuf = temporary_path("round_trip");
+        std::fs::write(&path, b"an older save").unwrap();
+
+        let mut world: World = World::new(64, 64, 7, BlockRegistry::load_default());
+        let stone: u16 = world.blocks.id("stone").unwrap();
+        let stone_wall: u16 = world.blocks.id("stone_wall").unwrap();
+        world.set_block(3, 40, stone);
+        world.set_wall(4, 41, stone_wall);
+        let mut player: Player = Player::new(&world);
+        player.inventory.add(stone, 5);
+
+        save(&world, &player, &path).unwrap();
+        let (loaded_world, loaded_player) = load(&path, BlockRegistry::load_default()).unwrap();
+        let mut temporary_file: std::ffi::OsString = path.as_os_str().to_owned();
+        temporary_file.push(".tmp");
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(!Path::new(&temporary_file).exists());
+        assert_eq!(loaded_world.seed, 7);
+        assert_eq!(loaded_world.get_block(3, 40), Some(stone));
+        assert_eq!(loaded_world.get_wall(4, 41), Some(stone_wall));
+        assert_eq!(loaded_player.inventory.slots[0], Some(ItemStack { block_id: stone, count: 5 }));
+    }
+
+    #[test]
+    fn loads_version_2_without_inventory_and_walls() {
+        let path: std::path::PathBuf = temporary_path("version_2");
+        let stone: u16 = BlockRegistry::load_default().id("stone").unwrap();
+
+        let mut bytes: Vec<u8> = Vec::new();
+        bytes.extend_from_slice(&MAGIC);
+        bytes.extend_from_slice(&2u16.to_le_bytes());
+        bytes.extend_from_slice(&64u32.to_le_bytes());
+        bytes.extend_from_slice(&64u32.to_le_bytes());
+        bytes.extend_from_slice(&9u64.to_le_bytes());
+
+        let mut encoder: ZlibEncoder<Vec<u8>> = ZlibEncoder::new(bytes, Compression::default());
+        for value in [90.0f32, 0.0, 60.0, 0.0] {
+            encoder.write_all(&value.to_le_bytes()).unwrap();
+        }
+        encoder.write_all(&1u32.to_le_bytes()).unwrap();
+        encoder.write_all(&0i32.to_le_bytes()).unwrap();
+        encoder.write_all(&1i32.to_le_bytes()).unwrap();
+        for _ in 0..Chunk::AREA {
+            encoder.write_all(&stone.to_le_bytes()).unwrap();
+        }
+        std::fs::write(&path, encoder.finish().unwrap()).unwrap();
+
+        let (world, player) = load(&path, BlockRegistry::load_default()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(world.seed, 9);
+        assert_eq!(world.get_block(0, Chunk::SIZE as i32), Some(stone));
+        assert_eq!(world.get_wall(0, Chunk::SIZE as i32), Some(AIR));
+        assert_eq!(player.position_x, 90.0);
+        assert_eq!((player.previous_position_x, player.previous_position_y), (90.0, 60.0));
+        assert!(player.inventory.slots.iter().all(|slot| slot.is_none()));
+    }
+
+    #[test]
+    fn rejects_stacks_larger_than_the_maximum() {
+        let path: std::path::PathBuf = temporary_path("oversized_stack");
+
+        let world: World = World::new(64, 64, 7, BlockRegistry::load_default());
+        let mut player: Player = Player::new(&world);
+        player.inventory.slots[0] = Some(ItemStack { block_id: world.blocks.id("stone").unwrap(), count: Inventory::MAX_STACK_SIZE + 1 });
+
+        save(&world, &player, &path).unwrap();
+        let result: std::io::Result<(World, Player)> = load(&path, BlockRegistry::load_default());
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(result.err().map(|error| error.kind()), Some(ErrorKind::InvalidData));
+    }
+}
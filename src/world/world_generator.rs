@@ -5,170 +5,402 @@
         ...
     ]
 */
-use rand::{Rng, SeedableRng};
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+use std::sync::mpsc::{Receiver, TryRecvError};
+use rand::SeedableRng;
 use crate::world::World;
-use crate::world::block_textures::BlockTypes;
+use crate::world::block_registry::BlockRegistry;
+use biome::{Biome, BiomeDefinition, BiomeMap, Vegetation};
+use noise::{NoiseConfig, NoiseKind};
 
-const MAP_WIDTH: u32 = World::MAP_WIDTH;
-const MAP_HEIGHT: u32 = World::MAP_HEIGHT;
-const MAP_AREA: u32 = MAP_WIDTH * MAP_HEIGHT;
+pub mod biome;
+pub mod noise;
+mod passes;
 
-pub fn generate_world() -> Box<[u16; MAP_AREA as usize]> {
-    const SEED: [u8; 32] = [1; 32];
+// separate every noise sampled by the generator from the others, see Noise::new
+const SURFACE_NOISE_SALT: u64 = 1;
+const CAVE_NOISE_SALT: u64 = 2;
+// ores use this salt plus their index in WorldGenConfig::ores
+const ORE_NOISE_SALT: u64 = 3;
 
-    let mut rng: rand::rngs::StdRng = rand::rngs::StdRng::from_seed(SEED);
+// one kind of ore and where its veins are placed
+#[derive(Clone, Debug)]
+pub struct OreConfig {
+    // block name in the block registry
+    pub block: String,
+    // blocks below the surface the center of a vein can be placed at
+    pub depth_range: RangeInclusive<u32>,
+    // number of random walk steps in one vein, a vein has at most this many blocks
+    pub vein_size_range: RangeInclusive<u32>,
+    // number of vein attempts per block of map width, attempts where the ore noise is negative are skipped
+    pub frequency: f32,
+}
 
-    let mut world_map: Box<[u16; MAP_AREA as usize]> = Box::new([0u16; MAP_AREA as usize]);
+#[derive(Clone, Debug)]
+pub struct WorldGenConfig {
+    pub seed: u64,
+    pub map_width: u32,
+    pub map_height: u32,
+    // height range of the surface before the biomes reshape it
+    pub surface_height_range: RangeInclusive<u32>,
+    // shape of the surface, more octaves add smaller hills on top of the large ones
+    pub surface_noise: NoiseConfig,
+    // number of subsurface blocks between the surface block and the underground block
+    pub dirt_depth: u32,
+    // width of the land biomes between the oceans
+    pub biome_width_range: RangeInclusive<u32>,
+    // number of columns over which two neighbouring biomes blend into each other
+    pub biome_blend_width: u32,
+    // width of the ocean at each map edge
+    pub ocean_width: u32,
+    // trees or cacti grown on the surface of each biome, biomes that are not listed stay bare
+    pub vegetation: Vec<(Biome, Vegetation)>,
+    // number of cave tunnels per block of map width
+    pub cave_density: f32,
+    // number of one block steps a cave tunnel travels, branches travel half of what is left
+    pub cave_length_range: RangeInclusive<u32>,
+    // tunnel radius at the top and at the bottom of the map, caves get wider with depth
+    pub cave_radius_range: RangeInclusive<f32>,
+    // varies the tunnel radius by up to half along the tunnel
+    pub cave_radius_noise: NoiseConfig,
+    // chance per step for a tunnel to open into a cavern and to split into a branch
+    pub cavern_chance: f32,
+    pub cave_branch_chance: f32,
+    // number of blocks below the surface that caves never carve, so the surface stays intact
+    pub cave_surface_margin: u32,
+    // ores replace stone, dirt, sandstone and mud, they are placed after the caves so veins never float in a cave
+    pub ores: Vec<OreConfig>,
+    // veins are only placed where the noise is positive, so every ore has rich and poor areas
+    pub ore_noise: NoiseConfig,
+    // number of buried wooden cabins per block of map width
+    pub cabin_density: f32,
+    // blocks below the surface the floor of a cabin can be placed at
+    pub cabin_depth_range: RangeInclusive<u32>,
+}
 
-    fn set_block(world_map: &mut Box<[u16; MAP_AREA as usize]>, x: u32, y: u32, block_type: BlockTypes) {
-        match world_map.get((x + y * MAP_WIDTH) as usize) {
-            Some(_) => world_map[(x + y * MAP_WIDTH) as usize] = block_type as u16,
-            None => {
-                println!("Error: Write to out of bounds coordinate X:{}, Y:{}", x, y);
-            },
-        }
+impl WorldGenConfig {
+    pub fn vegetation(&self, biome: Biome) -> &Vegetation {
+        self.vegetation.iter()
+            .find(|(vegetation_biome, _)| *vegetation_biome == biome)
+            .map_or(&Vegetation::None, |(_, vegetation)| vegetation)
     }
 
-    fn get_block(world_map: &Box<[u16; MAP_AREA as usize]>, x: u32, y: u32) -> Result<u16, ()> {
-        match world_map.get((x + y * MAP_WIDTH) as usize) {
-            Some(&result) => Ok(result),
-            None => {
-                println!("Error: Read to out of bounds coordinate X:{}, Y:{}", x, y);
-                Err(())
+    // numeric seeds are used as is, any other text is hashed so worlds can be shared by name
+    pub fn seed_from_str(seed: &str) -> u64 {
+        match seed.parse::<u64>() {
+            Ok(seed) => seed,
+            Err(_) => {
+                // FNV-1a, stable across platforms and compiler versions unlike the std hasher
+                let mut hash: u64 = 0xcbf29ce484222325;
+                for byte in seed.bytes() {
+                    hash ^= byte as u64;
+                    hash = hash.wrapping_mul(0x100000001b3);
+                }
+                hash
             },
         }
     }
 
-    // base ground
+    pub fn validate(&self) -> Result<(), String> {
+        if self.map_width < 2 || self.map_width > World::MAX_MAP_WIDTH {
+            return Err(format!("Map width must be between 2 and {}, got {}", World::MAX_MAP_WIDTH, self.map_width));
+        }
+        if self.map_height > World::MAX_MAP_HEIGHT {
+            return Err(format!("Map height must be at most {}, got {}", World::MAX_MAP_HEIGHT, self.map_height));
+        }
+        if self.map_height <= self.surface_height_range.end() + self.dirt_depth {
+            return Err(format!("Map height must be greater than {}, got {}", self.surface_height_range.end() + self.dirt_depth, self.map_height));
+        }
+        self.surface_noise.validate("Surface")?;
+        self.cave_radius_noise.validate("Cave radius")?;
+        self.ore_noise.validate("Ore")?;
+        if self.cabin_density < 0.0 || self.cabin_depth_range.is_empty() {
+            return Err(format!("Invalid cabin density {} or depth range {:?}", self.cabin_density, self.cabin_depth_range));
+        }
+        if self.map_height <= self.surface_height_range.start() + self.cave_surface_margin {
+            return Err(format!("Map height must be greater than {} to fit caves, got {}", self.surface_height_range.start() + self.cave_surface_margin, self.map_height));
+        }
+        if *self.cave_radius_range.start() <= 0.0 || self.cave_radius_range.is_empty() {
+            return Err(format!("Cave radius range must be positive and not empty, got {:?}", self.cave_radius_range));
+        }
+        if self.cave_length_range.is_empty() {
+            return Err(format!("Cave length range must not be empty, got {:?}", self.cave_length_range));
+        }
+        for (name, chance) in [("Cavern chance", self.cavern_chance), ("Cave branch chance", self.cave_branch_chance)] {
+            if !(0.0..=1.0).contains(&chance) {
+                return Err(format!("{} must be between 0 and 1, got {}", name, chance));
+            }
+        }
+        if *self.biome_width_range.start() == 0 || self.biome_width_range.is_empty() {
+            return Err(format!("Biome width range must be positive and not empty, got {:?}", self.biome_width_range));
+        }
+        for (biome, vegetation) in self.vegetation.iter() {
+            let valid: bool = match vegetation {
+                Vegetation::None => true,
+                Vegetation::Trees { density, height_range, canopy_size } => (0.0..=1.0).contains(density) && !height_range.is_empty() && *canopy_size > 0,
+                Vegetation::Cacti { density, height_range } => (0.0..=1.0).contains(density) && !height_range.is_empty(),
+            };
+            if !valid {
+                return Err(format!("Invalid vegetation config for {:?}", biome));
+            }
+        }
+        for ore in self.ores.iter() {
+            if ore.depth_range.is_empty() || ore.vein_size_range.is_empty() || ore.frequency < 0.0 {
+                return Err(format!("Invalid ore config for {}", ore.block));
+            }
+        }
 
-    /*for i in 0..100 {
-        world_map[(i * MAP_WIDTH) as usize..(i * MAP_WIDTH + MAP_WIDTH) as usize].copy_from_slice(&[BlockTypes::Air as u16; MAP_WIDTH as usize]);
+        Ok(())
     }
+}
 
-    world_map[(100 * MAP_WIDTH) as usize..(100 * MAP_WIDTH + MAP_WIDTH) as usize].copy_from_slice(&[BlockTypes::Grass as u16; MAP_WIDTH as usize]);
-    
-    for i in 101..107 {
-        world_map[(i * MAP_WIDTH) as usize..(i * MAP_WIDTH + MAP_WIDTH) as usize].copy_from_slice(&[BlockTypes::Dirt as u16; MAP_WIDTH as usize]);
+impl Default for WorldGenConfig {
+    fn default() -> Self {
+        Self {
+            seed: 1,
+            map_width: World::MAP_WIDTH,
+            map_height: World::MAP_HEIGHT,
+            surface_height_range: 80..=180,
+            surface_noise: NoiseConfig { kind: NoiseKind::Simplex, octaves: 5, frequency: 1.0 / 400.0, lacunarity: 2.0, persistence: 0.45 },
+            dirt_depth: 5,
+            biome_width_range: 250..=600,
+            biome_blend_width: 40,
+            ocean_width: 250,
+            vegetation: vec![
+                (Biome::Forest, Vegetation::Trees { density: 1.0 / 20.0, height_range: 5..=10, canopy_size: 5 }),
+                (Biome::Desert, Vegetation::Cacti { density: 1.0 / 30.0, height_range: 2..=4 }),
+                (Biome::Snow, Vegetation::Trees { density: 1.0 / 30.0, height_range: 6..=10, canopy_size: 4 }),
+                (Biome::Jungle, Vegetation::Trees { density: 1.0 / 8.0, height_range: 10..=18, canopy_size: 6 }),
+            ],
+            cave_density: 1.0 / 25.0,
+            cave_length_range: 80..=250,
+            cave_radius_range: 1.5..=4.0,
+            cave_radius_noise: NoiseConfig { kind: NoiseKind::Perlin, octaves: 2, frequency: 1.0 / 20.0, lacunarity: 2.0, persistence: 0.5 },
+            cavern_chance: 0.01,
+            cave_branch_chance: 0.005,
+            cave_surface_margin: 10,
+            ores: vec![
+                OreConfig { block: "coal_ore".to_string(), depth_range: 5..=150, vein_size_range: 6..=14, frequency: 1.0 / 4.0 },
+                OreConfig { block: "copper_ore".to_string(), depth_range: 10..=200, vein_size_range: 4..=10, frequency: 1.0 / 5.0 },
+                OreConfig { block: "iron_ore".to_string(), depth_range: 40..=300, vein_size_range: 4..=9, frequency: 1.0 / 7.0 },
+                OreConfig { block: "gold_ore".to_string(), depth_range: 120..=400, vein_size_range: 3..=7, frequency: 2.0 / 25.0 },
+            ],
+            ore_noise: NoiseConfig { kind: NoiseKind::Value, octaves: 3, frequency: 1.0 / 60.0, lacunarity: 2.0, persistence: 0.5 },
+            cabin_density: 1.0 / 250.0,
+            cabin_depth_range: 30..=250,
+        }
     }
+}
 
-    for i in 107..MAP_HEIGHT {
-        world_map[(i * MAP_WIDTH) as usize..(i * MAP_WIDTH + MAP_WIDTH) as usize].copy_from_slice(&[BlockTypes::Stone as u16; MAP_WIDTH as usize]);
-    }*/
+// block ids of a biome definition
+struct BiomePalette {
+    surface: u16,
+    subsurface: u16,
+    underground: u16,
+}
 
-    // surface shape
+// ids of the blocks placed by the generator, guaranteed to exist by BlockRegistry::REQUIRED_BLOCKS
+struct Palette {
+    air: u16,
+    log: u16,
+    leaves: u16,
+    cactus: u16,
+    planks: u16,
+    torch: u16,
+    water: u16,
+    dirt_wall: u16,
+    stone_wall: u16,
+    wood_wall: u16,
+    // blocks that ore veins can replace
+    ore_hosts: Vec<u16>,
+    // indexed by Biome as usize
+    biomes: Vec<BiomePalette>,
+}
 
-    // height range for random surface points, is not equal to maximum and minimum height
-    const HEIGHT_POINT_RANGE: std::ops::RangeInclusive<u32> = 75..=125;
-    // odd number, determines smoothness of the final surface, higher numbers will make the surface smoother
-    const FILTER_WIDTH: u32 = 3;
-    // number of random height points including map edges
-    const HEIGHT_POINT_COUNT: u32 = 2 + MAP_WIDTH / 150;
+impl Palette {
+    fn new(blocks: &BlockRegistry) -> Self {
+        let id = |name: &str| -> u16 { blocks.id(name).unwrap_or_else(|| panic!("Missing required block {}", name)) };
 
-    let mut surface_height_map: [u32; MAP_WIDTH as usize] = [0; MAP_WIDTH as usize];
+        let biomes: Vec<BiomePalette> = [Biome::Ocean, Biome::Forest, Biome::Desert, Biome::Snow, Biome::Jungle]
+            .into_iter()
+            .map(|biome| {
+                let definition: BiomeDefinition = biome.definition();
+                BiomePalette {
+                    surface: id(definition.surface_block),
+                    subsurface: id(definition.subsurface_block),
+                    underground: id(definition.underground_block),
+                }
+            })
+            .collect();
 
-    let mut random_height_points: [u32; HEIGHT_POINT_COUNT as usize] = [0; HEIGHT_POINT_COUNT as usize];
-    for i in 0..HEIGHT_POINT_COUNT {
-        random_height_points[i as usize] = rng.gen_range(HEIGHT_POINT_RANGE);
+        Self {
+            air: id("air"),
+            log: id("log"),
+            leaves: id("leaves"),
+            cactus: id("cactus"),
+            planks: id("planks"),
+            torch: id("torch"),
+            water: id("water"),
+            dirt_wall: id("dirt_wall"),
+            stone_wall: id("stone_wall"),
+            wood_wall: id("wood_wall"),
+            ore_hosts: vec![id("stone"), id("dirt"), id("sandstone"), id("mud")],
+            biomes,
+        }
     }
 
-    fn filter(x: f32, offset: f32) -> f32 {
-        if x == offset {
-            1.5
-        } else {
-            1.5 / (std::f32::consts::PI * (x - offset)) * f32::sin(std::f32::consts::PI * (x - offset))
-        }
+    fn biome(&self, biome: Biome) -> &BiomePalette {
+        &self.biomes[biome as usize]
     }
+}
 
-    for x in 0..MAP_WIDTH {
-        let mapped_x: f32 = x as f32 / (MAP_WIDTH - 1) as f32 * (HEIGHT_POINT_COUNT - 1) as f32;
-        let interpolated_y: f32 = random_height_points[mapped_x.floor() as usize] as f32 * filter(
-            mapped_x.floor(),
-            mapped_x,
-        );
-
-        let mut filter_value: f32 = 0.0;
-
-        for filter_x in mapped_x.floor() as u32 + 1..mapped_x.floor() as u32 + 1 + (FILTER_WIDTH - 1) / 2 {
-            if filter_x < HEIGHT_POINT_COUNT {
-                filter_value += random_height_points[filter_x as usize] as f32 * filter(
-                    filter_x as f32,
-                    mapped_x,
-                );
-            }
-        }
-        for filter_x in mapped_x.floor() as i32 - 1 - (FILTER_WIDTH - 1) as i32 / 2..mapped_x.floor() as i32 - 1 {
-            if filter_x >= 0 {
-                filter_value += random_height_points[filter_x as usize] as f32 * filter(
-                    filter_x as f32,
-                    mapped_x,
-                );
-            }
+// state shared by the passes of one generation run, every pass builds on the result of the passes before it
+pub struct GenerationContext<'a> {
+    pub config: &'a WorldGenConfig,
+    pub world: World,
+    // the only source of randomness besides the noises, shared so the passes always draw in the same order,
+    // ChaCha8Rng gives the same values for a seed in every version unlike StdRng
+    pub rng: rand_chacha::ChaCha8Rng,
+    // chosen before the first pass
+    pub biome_map: BiomeMap,
+    // first block of every column, set by the terrain pass
+    pub surface_height_map: Vec<u32>,
+    // oceans are filled with water up to this height, set by the terrain pass
+    pub sea_level: u32,
+    palette: Palette,
+}
+
+impl<'a> GenerationContext<'a> {
+    fn new(config: &'a WorldGenConfig, blocks: Arc<BlockRegistry>) -> Self {
+        let mut rng: rand_chacha::ChaCha8Rng = rand_chacha::ChaCha8Rng::seed_from_u64(config.seed);
+        let biome_map: BiomeMap = BiomeMap::generate(config, &mut rng);
+
+        Self {
+            config,
+            palette: Palette::new(&blocks),
+            world: World::new(config.map_width, config.map_height, config.seed, blocks),
+            rng,
+            biome_map,
+            surface_height_map: vec![0; config.map_width as usize],
+            sea_level: 0,
         }
+    }
+}
 
-        let surface_y: u32 = (interpolated_y + filter_value).round() as u32; 
+// one step of world generation, see passes::pipeline for the order
+pub trait GenerationPass {
+    // shown on the loading screen
+    fn name(&self) -> &'static str;
 
-        surface_height_map[x as usize] = surface_y;
+    // report_progress takes how much of the pass is done, from 0 to 1
+    fn run(&self, context: &mut GenerationContext, report_progress: &mut dyn FnMut(f32));
+}
 
-        set_block(&mut world_map, x, surface_y, BlockTypes::Grass);
+#[derive(Clone, Debug)]
+pub struct GenerationProgress {
+    pub pass_name: &'static str,
+    pub pass_index: usize,
+    pub pass_count: usize,
+    // progress of the current pass from 0 to 1
+    pub pass_progress: f32,
+}
 
-        for y in surface_y + 1..surface_y + 6 {
-            set_block(&mut world_map, x, y, BlockTypes::Dirt);
-        }
-        for y in surface_y + 6..MAP_HEIGHT {
-            set_block(&mut world_map, x, y, BlockTypes::Stone);
-        }
-    }
-    
-    // trees
-    const TREE_HEIGHT_RANGE: std::ops::RangeInclusive<u32> = 5..=10;
-
-    let tree_count: u32 = MAP_WIDTH / 20; // number of attempts to create a tree, 1 tree for every 20 blocks
-    for _ in 0..tree_count {
-        let tree_x: u32 = rng.gen_range(0..=MAP_WIDTH);
-        attempt_generate_surface_tree(&mut world_map, tree_x, &mut rng);
+impl GenerationProgress {
+    // progress of the whole pipeline from 0 to 1, every pass counts the same
+    pub fn fraction(&self) -> f32 {
+        ((self.pass_index as f32 + self.pass_progress) / self.pass_count.max(1) as f32).clamp(0.0, 1.0)
     }
+}
+
+// runs every pass in order on the calling thread, see WorldGenerationTask to generate in the background
+pub fn generate_world(config: &WorldGenConfig, blocks: Arc<BlockRegistry>, on_progress: &mut dyn FnMut(&GenerationProgress)) -> World {
+    let pipeline: Vec<Box<dyn GenerationPass>> = passes::pipeline();
+    let mut context: GenerationContext = GenerationContext::new(config, blocks);
+
+    for (pass_index, pass) in pipeline.iter().enumerate() {
+        // passes report often, only whole percents are passed on
+        let mut last_percent: Option<u32> = None;
+        let mut report_progress = |pass_progress: f32| {
+            let percent: u32 = (pass_progress.clamp(0.0, 1.0) * 100.0) as u32;
 
-    fn get_ground_y_if_clear(world_map: &Box<[u16; MAP_AREA as usize]>, x: u32) -> Option<u32> {
-        for y in 0..MAP_HEIGHT {
-            let target_block = get_block(&world_map, x, y);
-
-            match target_block {
-                Ok(target_block) => {
-                    if target_block != BlockTypes::Air as u16 {
-                        if target_block == BlockTypes::Grass as u16 {
-                            return Some(y);
-                        } else {
-                            return None;
-                        }
-                    }
-                },
-                Err(_) => return None,
+            if last_percent != Some(percent) {
+                last_percent = Some(percent);
+                on_progress(&GenerationProgress {
+                    pass_name: pass.name(),
+                    pass_index,
+                    pass_count: pipeline.len(),
+                    pass_progress,
+                });
             }
-        }
+        };
 
-        return None;
+        report_progress(0.0);
+        pass.run(&mut context, &mut report_progress);
     }
 
-    fn attempt_generate_surface_tree(world_map: &mut Box<[u16; MAP_AREA as usize]>, tree_x: u32, rng: &mut rand::rngs::StdRng) {
-        match get_ground_y_if_clear(world_map, tree_x) {
-            Some(tree_base_y) => {
-                let tree_height: u32 = rng.gen_range(TREE_HEIGHT_RANGE);
+    context.world
+}
 
-                set_block(world_map, tree_x, tree_base_y, BlockTypes::Dirt);
+enum GenerationMessage {
+    Progress(GenerationProgress),
+    Finished(World),
+}
 
-                for tree_segment_y in 0..tree_height {
-                    set_block(world_map, tree_x, tree_base_y - 1 - tree_segment_y, BlockTypes::Log);
-                }
+// generates a world on a background thread so the window keeps responding
+pub struct WorldGenerationTask {
+    receiver: Receiver<GenerationMessage>,
+    // the last progress received from the generation thread
+    pub progress: GenerationProgress,
+}
 
-                for leaf_y in 0..5 {
-                    for i in 0..leaf_y * 2 + 1 {
-                        set_block(world_map, tree_x + i - leaf_y, tree_base_y - 5 - tree_height + leaf_y, BlockTypes::Leaves);
-                    }
-                }
+impl WorldGenerationTask {
+    pub fn start(config: WorldGenConfig, blocks: Arc<BlockRegistry>) -> Self {
+        let (sender, receiver) = std::sync::mpsc::channel::<GenerationMessage>();
+
+        std::thread::spawn(move || {
+            // sending only fails once the receiver is gone, which means the game was closed and the world is not needed
+            let world: World = generate_world(&config, blocks, &mut |progress: &GenerationProgress| {
+                let _ = sender.send(GenerationMessage::Progress(progress.clone()));
+            });
+            let _ = sender.send(GenerationMessage::Finished(world));
+        });
+
+        Self {
+            receiver,
+            progress: GenerationProgress {
+                pass_name: "Starting",
+                pass_index: 0,
+                pass_count: 1,
+                pass_progress: 0.0,
             },
-            None => {}
         }
     }
 
-    world_map
-}
\ No newline at end of file
+    // returns the world once it is finished, an error means the generation thread panicked
+    pub fn poll(&mut self) -> Result<Option<World>, String> {
+        loop {
+            match self.receiver.try_recv() {
+                Ok(GenerationMessage::Progress(progress)) => self.progress = progress,
+                Ok(GenerationMessage::Finished(world)) => return Ok(Some(world)),
+                Err(TryRecvError::Empty) => return Ok(None),
+                Err(TryRecvError::Disconnected) => return Err("World generation stopped unexpectedly".to_string()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_rejects_maps_too_large_for_the_coordinates() {
+        assert!(WorldGenConfig::default().validate().is_ok());
+
+        for (map_width, map_height) in [(3_000_000_000, World::MAP_HEIGHT), (World::MAX_MAP_WIDTH + 1, World::MAP_HEIGHT), (World::MAP_WIDTH, World::MAX_MAP_HEIGHT + 1)] {
+            let config: WorldGenConfig = WorldGenConfig { map_width, map_height, ..WorldGenConfig::default() };
+            assert!(config.validate().is_err(), "{}x{}", map_width, map_height);
+        }
+    }
+}
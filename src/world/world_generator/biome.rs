@@ -0,0 +1,172 @@
+use std::ops::RangeInclusive;
+use rand::Rng;
+use crate::world::world_generator::WorldGenConfig;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Biome {
+    Ocean,
+    Forest,
+    Desert,
+    Snow,
+    Jungle,
+}
+
+#[derive(Clone, Debug)]
+pub enum Vegetation {
+    None,
+    // log trunk with a triangle of leaves on top, canopy_size is the number of leaf rows
+    // density is the chance per surface column
+    Trees { density: f32, height_range: RangeInclusive<u32>, canopy_size: u32 },
+    Cacti { density: f32, height_range: RangeInclusive<u32> },
+}
+
+// what a biome places on and below its surface, blocks are names in the block registry,
+// what grows on top of it is set per biome in WorldGenConfig::vegetation
+#[derive(Clone, Debug)]
+pub struct BiomeDefinition {
+    pub surface_block: &'static str,
+    // fills the dirt depth below the surface block
+    pub subsurface_block: &'static str,
+    pub underground_block: &'static str,
+    // blocks added to the surface height, positive values lower the surface
+    pub height_offset: f32,
+    // scales how far the surface strays from the middle of the surface height range
+    pub height_variation: f32,
+}
+
+impl Biome {
+    // biomes placed between the oceans at the map edges
+    pub const LAND: [Biome; 4] = [Biome::Forest, Biome::Desert, Biome::Snow, Biome::Jungle];
+
+    pub fn definition(self) -> BiomeDefinition {
+        match self {
+            Biome::Ocean => BiomeDefinition {
+                surface_block: "sand",
+                subsurface_block: "sand",
+                underground_block: "stone",
+                height_offset: 30.0,
+                height_variation: 0.2,
+            },
+            Biome::Forest => BiomeDefinition {
+                surface_block: "grass",
+                subsurface_block: "dirt",
+                underground_block: "stone",
+                height_offset: 0.0,
+                height_variation: 1.0,
+            },
+            Biome::Desert => BiomeDefinition {
+                surface_block: "sand",
+                subsurface_block: "sand",
+                underground_block: "sandstone",
+                height_offset: 5.0,
+                height_variation: 0.4,
+            },
+            Biome::Snow => BiomeDefinition {
+                surface_block: "snow",
+                subsurface_block: "snow",
+                underground_block: "stone",
+                height_offset: -10.0,
+                height_variation: 1.4,
+            },
+            Biome::Jungle => BiomeDefinition {
+                surface_block: "jungle_grass",
+                subsurface_block: "mud",
+                underground_block: "stone",
+                height_offset: 0.0,
+                height_variation: 0.8,
+            },
+        }
+    }
+}
+
+// biomes as spans of columns from left to right, neighbouring spans blend into each other at their border
+pub struct BiomeMap {
+    // biome and the first column of every span, the first span starts at column 0
+    spans: Vec<(Biome, u32)>,
+    width: u32,
+    blend_width: u32,
+}
+
+impl BiomeMap {
+    pub fn generate(config: &WorldGenConfig, rng: &mut rand_chacha::ChaCha8Rng) -> Self {
+        let width: u32 = config.map_width;
+        let ocean_width: u32 = config.ocean_width.min(width / 2);
+
+        let mut spans: Vec<(Biome, u32)> = vec![(Biome::Ocean, 0)];
+        let mut x: u32 = ocean_width;
+        while x < width - ocean_width {
+            // the same biome twice in a row would just be one wider span
+            let previous_biome: Biome = spans.last().expect("spans start with an ocean").0;
+            let biome: Biome = loop {
+                let biome: Biome = Biome::LAND[rng.gen_range(0..Biome::LAND.len())];
+                if biome != previous_biome {
+                    break biome;
+                }
+            };
+
+            spans.push((biome, x));
+            x += rng.gen_range(config.biome_width_range.clone());
+        }
+        spans.push((Biome::Ocean, width - ocean_width));
+
+        Self {
+            spans,
+            width,
+            blend_width: config.biome_blend_width,
+        }
+    }
+
+    // every biome with influence on the column and its weight, the weights add up to 1
+    pub fn weights(&self, x: u32) -> Vec<(Biome, f32)> {
+        let mut weights: Vec<(Biome, f32)> = Vec::new();
+
+        for (index, &(biome, start)) in self.spans.iter().enumerate() {
+            let end: u32 = self.spans.get(index + 1).map_or(self.width, |&(_, next_start)| next_start);
+
+            // distance to the nearest border, negative outside of the span, the map edges are not borders
+            let distance_to_start: f32 = if index == 0 { f32::INFINITY } else { x as f32 + 0.5 - start as f32 };
+            let distance_to_end: f32 = if index + 1 == self.spans.len() { f32::INFINITY } else { end as f32 - x as f32 - 0.5 };
+            let distance: f32 = distance_to_start.min(distance_to_end);
+
+            let weight: f32 = if self.blend_width == 0 {
+                if distance > 0.0 { 1.0 } else { 0.0 }
+            } else {
+                (0.5 + distance / self.blend_width as f32).clamp(0.0, 1.0)
+            };
+
+            if weight > 0.0 {
+                weights.push((biome, weight));
+            }
+        }
+
+        let total_weight: f32 = weights.iter().map(|&(_, weight)| weight).sum();
+        for (_, weight) in weights.iter_mut() {
+            *weight /= total_weight;
+        }
+
+        weights
+    }
+
+    // the biome with the most weight, used for things that can not be blended like vegetation
+    pub fn dominant(&self, x: u32) -> Biome {
+        self.weights(x)
+            .into_iter()
+            .max_by(|(_, weight_a), (_, weight_b)| weight_a.total_cmp(weight_b))
+            .map_or(Biome::Ocean, |(biome, _)| biome)
+    }
+
+    // picks a biome with a chance equal to its weight, so the blocks of neighbouring biomes mix at the border
+    pub fn sample(&self, x: u32, rng: &mut rand_chacha::ChaCha8Rng) -> Biome {
+        let weights: Vec<(Biome, f32)> = self.weights(x);
+        let mut remaining: f32 = rng.gen_range(0.0..1.0);
+
+        for &(biome, weight) in weights.iter() {
+            if remaining < weight {
+                return biome;
+            }
+            remaining -= weight;
+        }
+
+        weights.last().map_or(Biome::Ocean, |&(biome, _)| biome)
+    }
+}
@@ -0,0 +1,296 @@
+use rand::{RngCore, SeedableRng};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NoiseKind {
+    // random values on the lattice, blocky at low octave counts
+    Value,
+    // gradient noise on a square lattice
+    Perlin,
+    // gradient noise on a triangle lattice, fewer directional artifacts than perlin
+    Simplex,
+}
+
+#[derive(Clone, Debug)]
+pub struct NoiseConfig {
+    pub kind: NoiseKind,
+    // number of layers added together, every layer adds finer detail
+    pub octaves: u32,
+    // lattice cells per block for the first octave
+    pub frequency: f32,
+    // frequency multiplier from one octave to the next
+    pub lacunarity: f32,
+    // amplitude multiplier from one octave to the next
+    pub persistence: f32,
+}
+
+impl NoiseConfig {
+    pub fn validate(&self, name: &str) -> Result<(), String> {
+        if self.octaves == 0 {
+            return Err(format!("{} noise needs at least one octave", name));
+        }
+        if self.frequency <= 0.0 || self.lacunarity <= 0.0 || self.persistence <= 0.0 {
+            return Err(format!("{} noise frequency, lacunarity and persistence must be positive", name));
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for NoiseConfig {
+    fn default() -> Self {
+        Self {
+            kind: NoiseKind::Simplex,
+            octaves: 4,
+            frequency: 1.0 / 100.0,
+            lacunarity: 2.0,
+            persistence: 0.5,
+        }
+    }
+}
+
+// fractal noise, the same seed and config always give the same values
+pub struct Noise {
+    config: NoiseConfig,
+    // shuffled 0..256 repeated twice so lookups of a lookup do not need to wrap
+    permutation: Vec<u8>,
+}
+
+impl Noise {
+    // the salt separates noises that share the world seed, like the surface and the caves
+    pub fn new(seed: u64, salt: u64, config: NoiseConfig) -> Self {
+        let mut rng: rand_chacha::ChaCha8Rng = rand_chacha::ChaCha8Rng::seed_from_u64(seed ^ salt.wrapping_mul(0x9e3779b97f4a7c15));
+
+        // shuffled by hand from the raw output of the generator, which unlike SliceRandom::shuffle never changes between versions,
+        // so a shared seed keeps giving the same world
+        let mut values: Vec<u8> = (0..=255).collect();
+        for index in (1..values.len()).rev() {
+            let swap_index: usize = (rng.next_u64() % (index as u64 + 1)) as usize;
+            values.swap(index, swap_index);
+        }
+
+        let mut permutation: Vec<u8> = values.clone();
+        permutation.extend_from_slice(&values);
+
+        Self {
+            config,
+            permutation,
+        }
+    }
+
+    pub fn sample_1d(&self, x: f32) -> f32 {
+        self.sample_2d(x, 0.0)
+    }
+
+    // sum of all octaves, between -1 and 1
+    pub fn sample_2d(&self, x: f32, y: f32) -> f32 {
+        let mut value: f32 = 0.0;
+        let mut total_amplitude: f32 = 0.0;
+        let mut frequency: f32 = self.config.frequency;
+        let mut amplitude: f32 = 1.0;
+
+        for octave in 0..self.config.octaves {
+            // every octave is moved away from the others so their lattice points do not line up
+            let offset: f32 = octave as f32 * 31.7;
+            let (sample_x, sample_y) = (x * frequency + offset, y * frequency + offset);
+
+            value += amplitude * match self.config.kind {
+                NoiseKind::Value => self.value(sample_x, sample_y),
+                NoiseKind::Perlin => self.perlin(sample_x, sample_y),
+                NoiseKind::Simplex => self.simplex(sample_x, sample_y),
+            };
+            total_amplitude += amplitude;
+
+            frequency *= self.config.lacunarity;
+            amplitude *= self.config.persistence;
+        }
+
+        // every octave is between -1 and 1, so their weighted mean is too
+        value / total_amplitude
+    }
+
+    fn hash(&self, x: i32, y: i32) -> u8 {
+        let x_index: usize = (x & 255) as usize;
+        let y_index: usize = (y & 255) as usize;
+
+        self.permutation[self.permutation[x_index] as usize + y_index]
+    }
+
+    fn value(&self, x: f32, y: f32) -> f32 {
+        let (cell_x, cell_y) = (x.floor() as i32, y.floor() as i32);
+        let (fraction_x, fraction_y) = (fade(x - x.floor()), fade(y - y.floor()));
+
+        let corner = |offset_x: i32, offset_y: i32| -> f32 { self.hash(cell_x + offset_x, cell_y + offset_y) as f32 / 127.5 - 1.0 };
+
+        lerp(
+            lerp(corner(0, 0), corner(1, 0), fraction_x),
+            lerp(corner(0, 1), corner(1, 1), fraction_x),
+            fraction_y,
+        )
+    }
+
+    fn perlin(&self, x: f32, y: f32) -> f32 {
+        let (cell_x, cell_y) = (x.floor() as i32, y.floor() as i32);
+        let (local_x, local_y) = (x - x.floor(), y - y.floor());
+
+        let corner = |offset_x: i32, offset_y: i32| -> f32 {
+            gradient(self.hash(cell_x + offset_x, cell_y + offset_y), local_x - offset_x as f32, local_y - offset_y as f32)
+        };
+
+        let (fraction_x, fraction_y) = (fade(local_x), fade(local_y));
+
+        lerp(
+            lerp(corner(0, 0), corner(1, 0), fraction_x),
+            lerp(corner(0, 1), corner(1, 1), fraction_x),
+            fraction_y,
+        )
+    }
+
+    fn simplex(&self, x: f32, y: f32) -> f32 {
+        // skew and unskew factors between the square and the triangle lattice
+        let skew: f32 = 0.5 * (3.0_f32.sqrt() - 1.0);
+        let unskew: f32 = (3.0 - 3.0_f32.sqrt()) / 6.0;
+
+        let skewed: f32 = (x + y) * skew;
+        let (cell_x, cell_y) = ((x + skewed).floor() as i32, (y + skewed).floor() as i32);
+        let unskewed: f32 = (cell_x + cell_y) as f32 * unskew;
+        let (local_x, local_y) = (x - (cell_x as f32 - unskewed), y - (cell_y as f32 - unskewed));
+
+        // the middle corner of the triangle the point is in
+        let (middle_x, middle_y) = if local_x > local_y { (1, 0) } else { (0, 1) };
+
+        let corners: [(i32, i32, f32, f32); 3] = [
+            (0, 0, local_x, local_y),
+            (middle_x, middle_y, local_x - middle_x as f32 + unskew, local_y - middle_y as f32 + unskew),
+            (1, 1, local_x - 1.0 + 2.0 * unskew, local_y - 1.0 + 2.0 * unskew),
+        ];
+
+        let mut value: f32 = 0.0;
+        for (offset_x, offset_y, distance_x, distance_y) in corners {
+            let falloff: f32 = 0.5 - distance_x * distance_x - distance_y * distance_y;
+            if falloff > 0.0 {
+                value += falloff.powi(4) * gradient(self.hash(cell_x + offset_x, cell_y + offset_y), distance_x, distance_y);
+            }
+        }
+
+        // scales the sum of the corners to about -1 to 1
+        value * 70.0
+    }
+}
+
+// smooth curve from 0 to 1 with zero slope and curvature at both ends
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+// dot product of the offset with one of eight gradient directions picked by the hash
+fn gradient(hash: u8, x: f32, y: f32) -> f32 {
+    match hash & 7 {
+        0 => x + y,
+        1 => x - y,
+        2 => -x + y,
+        3 => -x - y,
+        4 => x,
+        5 => -x,
+        6 => y,
+        _ => -y,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KINDS: [NoiseKind; 3] = [NoiseKind::Value, NoiseKind::Perlin, NoiseKind::Simplex];
+
+    fn config(kind: NoiseKind) -> NoiseConfig {
+        NoiseConfig { kind, octaves: 4, frequency: 1.0 / 20.0, lacunarity: 2.0, persistence: 0.5 }
+    }
+
+    // a grid of points, offset so they do not all fall on lattice corners
+    fn samples(noise: &Noise) -> Vec<f32> {
+        (0..64).flat_map(|x| (0..64).map(move |y| noise.sample_2d(x as f32 * 3.7 + 0.3, y as f32 * 2.9 - 50.1))).collect()
+    }
+
+    #[test]
+    fn same_seed_salt_and_config_give_the_same_samples() {
+        for kind in KINDS {
+            let first: Vec<f32> = samples(&Noise::new(42, 1, config(kind)));
+            let second: Vec<f32> = samples(&Noise::new(42, 1, config(kind)));
+
+            assert_eq!(first, second, "{:?}", kind);
+        }
+    }
+
+    // values recorded for a fixed seed and salt, a change here changes every world generated from a shared seed
+    #[test]
+    fn fixed_seed_and_salt_give_the_recorded_samples() {
+        let recorded_samples: [(NoiseKind, [f32; 3]); 3] = [
+            (NoiseKind::Value, [-0.32806423, 0.044761267, 0.35244927]),
+            (NoiseKind::Perlin, [0.07961234, -0.21486798, -0.14953157]),
+            (NoiseKind::Simplex, [0.13673252, -0.02716056, 0.38702926]),
+        ];
+
+        for (kind, recorded) in recorded_samples {
+            let noise: Noise = Noise::new(12345, 1, config(kind));
+            assert_eq!(noise.permutation[..8], [216, 160, 95, 143, 53, 97, 168, 90]);
+
+            let sampled: [f32; 3] = [(0.5, 0.5), (17.3, -4.1), (250.0, 99.9)].map(|(x, y)| noise.sample_2d(x, y));
+            for (sample, recorded_sample) in sampled.into_iter().zip(recorded) {
+                assert!((sample - recorded_sample).abs() < 1e-5, "{:?} sampled {}, recorded {}", kind, sample, recorded_sample);
+            }
+        }
+    }
+
+    #[test]
+    fn different_salts_give_different_samples() {
+        for kind in KINDS {
+            let first: Vec<f32> = samples(&Noise::new(42, 1, config(kind)));
+            let second: Vec<f32> = samples(&Noise::new(42, 2, config(kind)));
+
+            let differing_count: usize = first.iter().zip(second.iter()).filter(|(a, b)| a != b).count();
+            assert!(differing_count > first.len() / 2, "{:?} only differs in {} of {} samples", kind, differing_count, first.len());
+        }
+    }
+
+    #[test]
+    fn samples_stay_within_range() {
+        for kind in KINDS {
+            for seed in 0..4 {
+                let noise: Noise = Noise::new(seed, 3, config(kind));
+
+                for sample in samples(&noise) {
+                    assert!((-1.0..=1.0).contains(&sample), "{:?} sampled {}", kind, sample);
+                }
+            }
+        }
+    }
+
+    // a single octave of every kind, sampled densely across many lattice cells
+    #[test]
+    fn every_kind_stays_within_range_on_its_own() {
+        for seed in 0..4 {
+            let noise: Noise = Noise::new(seed, 3, NoiseConfig::default());
+            let mut extremes: [(f32, f32); 3] = [(0.0, 0.0); 3];
+
+            for x in 0..400 {
+                for y in 0..400 {
+                    let (sample_x, sample_y) = (x as f32 * 0.1 + 0.05, y as f32 * 0.1 + 0.05);
+
+                    for (kind_index, value) in [noise.value(sample_x, sample_y), noise.perlin(sample_x, sample_y), noise.simplex(sample_x, sample_y)].into_iter().enumerate() {
+                        assert!((-1.0..=1.0).contains(&value), "{:?} sampled {} at {}, {}", KINDS[kind_index], value, sample_x, sample_y);
+                        extremes[kind_index] = (extremes[kind_index].0.min(value), extremes[kind_index].1.max(value));
+                    }
+                }
+            }
+
+            // the scaling uses most of the range, so a wrong factor is noticed
+            for (kind_index, (min, max)) in extremes.into_iter().enumerate() {
+                assert!(min < -0.5 && max > 0.5, "{:?} only reached {} to {}", KINDS[kind_index], min, max);
+            }
+        }
+    }
+}
@@ -0,0 +1,86 @@
+mod terrain;
+mod caves;
+mod ores;
+mod liquids;
+mod structures;
+mod vegetation;
+mod spawn_point;
+mod lighting;
+
+use crate::world::World;
+use crate::world::world_generator::{GenerationPass, Palette};
+#[cfg(test)]
+use crate::world::block_registry::BlockRegistry;
+#[cfg(test)]
+use crate::world::world_generator::{GenerationContext, WorldGenConfig};
+
+// every pass builds on the ones before it, so the order matters
+pub fn pipeline() -> Vec<Box<dyn GenerationPass>> {
+    vec![
+        Box::new(terrain::TerrainPass),
+        Box::new(caves::CavesPass),
+        Box::new(ores::OresPass),
+        Box::new(liquids::LiquidsPass),
+        Box::new(structures::StructuresPass),
+        Box::new(vegetation::VegetationPass),
+        Box::new(spawn_point::SpawnPointPass),
+        Box::new(lighting::LightingPass),
+    ]
+}
+
+// a context for the config with the given passes already run, so a test can check the pass that follows them
+#[cfg(test)]
+fn test_context<'a>(config: &'a WorldGenConfig, passes: &[&dyn GenerationPass]) -> GenerationContext<'a> {
+    config.validate().expect("test config is valid");
+
+    let mut context: GenerationContext = GenerationContext::new(config, BlockRegistry::load_default());
+    for pass in passes {
+        pass.run(&mut context, &mut |_| {});
+    }
+
+    context
+}
+
+fn set_block(world: &mut World, x: u32, y: u32, block_type: u16) {
+    if world.set_block(x as i32, y as i32, block_type).is_none() {
+        println!("Error: Write to out of bounds coordinate X:{}, Y:{}", x, y);
+    }
+}
+
+fn set_wall(world: &mut World, x: u32, y: u32, wall_type: u16) {
+    if world.set_wall(x as i32, y as i32, wall_type).is_none() {
+        println!("Error: Write to out of bounds coordinate X:{}, Y:{}", x, y);
+    }
+}
+
+fn get_block(world: &World, x: u32, y: u32) -> Result<u16, ()> {
+    match world.get_block(x as i32, y as i32) {
+        Some(block_type) => Ok(block_type),
+        None => {
+            println!("Error: Read to out of bounds coordinate X:{}, Y:{}", x, y);
+            Err(())
+        },
+    }
+}
+
+// the first block from the top, if it is the given ground block
+fn get_ground_y_if_clear(world: &World, palette: &Palette, ground_block: u16, x: u32) -> Option<u32> {
+    for y in 0..world.height {
+        let target_block = get_block(world, x, y);
+
+        match target_block {
+            Ok(target_block) => {
+                if target_block != palette.air {
+                    if target_block == ground_block {
+                        return Some(y);
+                    } else {
+                        return None;
+                    }
+                }
+            },
+            Err(_) => return None,
+        }
+    }
+
+    None
+}
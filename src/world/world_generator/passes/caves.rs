@@ -0,0 +1,109 @@
+use rand::Rng;
+use crate::world::World;
+use crate::world::world_generator::{GenerationContext, GenerationPass, Palette, WorldGenConfig, CAVE_NOISE_SALT};
+use crate::world::world_generator::noise::Noise;
+use crate::world::world_generator::passes::set_block;
+
+// carves branching tunnels and caverns below the surface margin
+pub struct CavesPass;
+
+impl GenerationPass for CavesPass {
+    fn name(&self) -> &'static str {
+        "Caves"
+    }
+
+    fn run(&self, context: &mut GenerationContext, report_progress: &mut dyn FnMut(f32)) {
+        let config = context.config;
+
+        let cave_count: u32 = (config.map_width as f32 * config.cave_density) as u32;
+        let cave_radius_noise: Noise = Noise::new(config.seed, CAVE_NOISE_SALT, config.cave_radius_noise.clone());
+        for cave_index in 0..cave_count {
+            generate_cave(&mut context.world, &context.palette, config, &cave_radius_noise, &context.surface_height_map, &mut context.rng);
+
+            report_progress(cave_index as f32 / cave_count as f32);
+        }
+    }
+}
+
+// walks a tunnel that slowly changes direction, carving a circle every step
+fn generate_cave(world: &mut World, palette: &Palette, config: &WorldGenConfig, radius_noise: &Noise, surface_height_map: &[u32], rng: &mut rand_chacha::ChaCha8Rng) {
+    let start_x: f32 = rng.gen_range(0..world.width) as f32;
+    let start_y: f32 = rng.gen_range(config.surface_height_range.start() + config.cave_surface_margin..world.height) as f32;
+    let length: u32 = rng.gen_range(config.cave_length_range.clone());
+
+    // tunnels still to be walked as x, y, direction and length, branches are added while walking
+    let mut tunnels: Vec<(f32, f32, f32, u32)> = vec![(start_x, start_y, rng.gen_range(0.0..std::f32::consts::TAU), length)];
+
+    while let Some((mut x, mut y, mut direction, length)) = tunnels.pop() {
+        for step in 0..length {
+            direction += rng.gen_range(-0.3..0.3);
+            x += direction.cos();
+            y += direction.sin();
+
+            if x < 0.0 || y < 0.0 || x >= world.width as f32 || y >= world.height as f32 {
+                break;
+            }
+
+            let depth: f32 = (y / world.height as f32).clamp(0.0, 1.0);
+            let mut radius: f32 = config.cave_radius_range.start() + (config.cave_radius_range.end() - config.cave_radius_range.start()) * depth;
+            radius *= 1.0 + radius_noise.sample_2d(x, y) / 2.0;
+
+            if rng.gen_bool(config.cavern_chance as f64) {
+                radius *= 2.5;
+            }
+            if rng.gen_bool(config.cave_branch_chance as f64) {
+                let branch_direction: f32 = direction + if rng.gen_bool(0.5) { std::f32::consts::FRAC_PI_2 } else { -std::f32::consts::FRAC_PI_2 };
+                tunnels.push((x, y, branch_direction, (length - step) / 2));
+            }
+
+            carve_circle(world, palette, config, surface_height_map, x, y, radius);
+        }
+    }
+}
+
+// replaces blocks with air and keeps the walls, so caves stay dark
+fn carve_circle(world: &mut World, palette: &Palette, config: &WorldGenConfig, surface_height_map: &[u32], center_x: f32, center_y: f32, radius: f32) {
+    for x in (center_x - radius).floor() as i32..=(center_x + radius).ceil() as i32 {
+        for y in (center_y - radius).floor() as i32..=(center_y + radius).ceil() as i32 {
+            if (x as f32 + 0.5 - center_x).hypot(y as f32 + 0.5 - center_y) > radius {
+                continue;
+            }
+
+            let Some(&surface_y) = usize::try_from(x).ok().and_then(|x| surface_height_map.get(x)) else {
+                continue;
+            };
+
+            if y > (surface_y + config.cave_surface_margin) as i32 && world.is_in_bounds(x, y) {
+                set_block(world, x as u32, y as u32, palette.air);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::world_generator::passes::test_context;
+    use crate::world::world_generator::passes::terrain::TerrainPass;
+
+    #[test]
+    fn caves_stay_below_the_surface_margin() {
+        let config: WorldGenConfig = WorldGenConfig { seed: 7, map_width: 800, map_height: 300, cave_density: 1.0 / 5.0, ..WorldGenConfig::default() };
+        let context: GenerationContext = test_context(&config, &[&TerrainPass, &CavesPass]);
+
+        // the terrain pass leaves every column solid from its surface down, so any air below the surface was carved
+        let mut carved_cell_count: u32 = 0;
+        for x in 0..config.map_width {
+            let surface_y: u32 = context.surface_height_map[x as usize];
+
+            for y in surface_y..config.map_height {
+                if context.world.get_block(x as i32, y as i32) == Some(context.palette.air) {
+                    assert!(y > surface_y + config.cave_surface_margin, "carved ({}, {}) within the margin below the surface at {}", x, y, surface_y);
+                    carved_cell_count += 1;
+                }
+            }
+        }
+
+        assert!(carved_cell_count > 0, "no caves were carved");
+    }
+}
@@ -0,0 +1,16 @@
+use crate::world::world_generator::{GenerationContext, GenerationPass};
+
+// lights the finished world, every other pass writes without relighting
+pub struct LightingPass;
+
+impl GenerationPass for LightingPass {
+    fn name(&self) -> &'static str {
+        "Lighting"
+    }
+
+    fn run(&self, context: &mut GenerationContext, _report_progress: &mut dyn FnMut(f32)) {
+        // nothing has been drawn yet, so the chunks written during generation do not need to be tracked
+        context.world.take_changed_chunks();
+        context.world.calculate_lighting();
+    }
+}
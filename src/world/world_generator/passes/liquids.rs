@@ -0,0 +1,27 @@
+use crate::world::world_generator::{GenerationContext, GenerationPass};
+use crate::world::world_generator::biome::Biome;
+use crate::world::world_generator::passes::set_block;
+
+// fills the oceans with water up to the sea level
+pub struct LiquidsPass;
+
+impl GenerationPass for LiquidsPass {
+    fn name(&self) -> &'static str {
+        "Liquids"
+    }
+
+    fn run(&self, context: &mut GenerationContext, report_progress: &mut dyn FnMut(f32)) {
+        let map_width: u32 = context.config.map_width;
+
+        for x in 0..map_width {
+            // the blended border counts as ocean too, so the water reaches the shore
+            if context.biome_map.weights(x).iter().any(|&(biome, _)| biome == Biome::Ocean) {
+                for y in context.sea_level..context.surface_height_map[x as usize] {
+                    set_block(&mut context.world, x, y, context.palette.water);
+                }
+            }
+
+            report_progress(x as f32 / map_width as f32);
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use rand::Rng;
+use crate::world::World;
+use crate::world::world_generator::{GenerationContext, GenerationPass, OreConfig, Palette, ORE_NOISE_SALT};
+use crate::world::world_generator::noise::Noise;
+use crate::world::world_generator::passes::set_block;
+
+// places the veins of every ore in WorldGenConfig::ores
+pub struct OresPass;
+
+impl GenerationPass for OresPass {
+    fn name(&self) -> &'static str {
+        "Ores"
+    }
+
+    fn run(&self, context: &mut GenerationContext, report_progress: &mut dyn FnMut(f32)) {
+        let config = context.config;
+
+        for (ore_index, ore) in config.ores.iter().enumerate() {
+            report_progress(ore_index as f32 / config.ores.len() as f32);
+
+            let Some(ore_block) = context.world.blocks.id(&ore.block) else {
+                println!("Error: Unknown ore block {}", ore.block);
+                continue;
+            };
+
+            // every ore gets its own rich and poor areas
+            let ore_noise: Noise = Noise::new(config.seed, ORE_NOISE_SALT + ore_index as u64, config.ore_noise.clone());
+
+            let vein_count: u32 = (config.map_width as f32 * ore.frequency) as u32;
+            for _ in 0..vein_count {
+                generate_ore_vein(&mut context.world, &context.palette, ore, ore_block, &ore_noise, &context.surface_height_map, &mut context.rng);
+            }
+        }
+    }
+}
+
+// random walk from the vein center, only the ore host blocks are replaced
+fn generate_ore_vein(
+    world: &mut World,
+    palette: &Palette,
+    ore: &OreConfig,
+    ore_block: u16,
+    ore_noise: &Noise,
+    surface_height_map: &[u32],
+    rng: &mut rand_chacha::ChaCha8Rng,
+) {
+    let mut x: i32 = rng.gen_range(0..world.width) as i32;
+    let mut y: i32 = (surface_height_map[x as usize] + rng.gen_range(ore.depth_range.clone())) as i32;
+
+    if ore_noise.sample_2d(x as f32, y as f32) < 0.0 {
+        return;
+    }
+
+    for _ in 0..rng.gen_range(ore.vein_size_range.clone()) {
+        if let Some(block_type) = world.get_block(x, y) {
+            if palette.ore_hosts.contains(&block_type) {
+                set_block(world, x as u32, y as u32, ore_block);
+            }
+        }
+
+        x += rng.gen_range(-1..=1);
+        y += rng.gen_range(-1..=1);
+    }
+}
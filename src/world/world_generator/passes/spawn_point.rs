@@ -0,0 +1,57 @@
+use crate::world::World;
+use crate::world::world_generator::{GenerationContext, GenerationPass};
+
+// picks the solid ground closest to the middle of the map, so the player does not spawn in water or a tree
+pub struct SpawnPointPass;
+
+impl GenerationPass for SpawnPointPass {
+    fn name(&self) -> &'static str {
+        "Spawn point"
+    }
+
+    fn run(&self, context: &mut GenerationContext, _report_progress: &mut dyn FnMut(f32)) {
+        let world: &mut World = &mut context.world;
+        let middle_x: i32 = world.width as i32 / 2;
+
+        for distance in 0..=middle_x {
+            for x in [middle_x - distance, middle_x + distance] {
+                let ground: Option<(i32, u16)> = (0..world.height as i32).find_map(|y| {
+                    world.get_block(x, y).filter(|&block_type| block_type != context.palette.air).map(|block_type| (y, block_type))
+                });
+
+                if let Some((ground_y, ground_block)) = ground {
+                    if world.blocks.is_solid(ground_block) {
+                        world.spawn_point = (x, ground_y);
+                        return;
+                    }
+                }
+            }
+        }
+
+        // narrow maps can be all ocean, the player then spawns on the sea floor in the middle
+        let sea_floor_y: Option<i32> = (0..world.height as i32).find(|&y| world.get_block(middle_x, y).is_some_and(|block_type| world.blocks.is_solid(block_type)));
+        if let Some(sea_floor_y) = sea_floor_y {
+            world.spawn_point = (middle_x, sea_floor_y);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::world_generator::WorldGenConfig;
+    use crate::world::world_generator::passes::test_context;
+    use crate::world::world_generator::passes::liquids::LiquidsPass;
+    use crate::world::world_generator::passes::terrain::TerrainPass;
+
+    #[test]
+    fn all_ocean_map_spawns_on_the_sea_floor() {
+        let config: WorldGenConfig = WorldGenConfig { map_width: 200, map_height: 250, ..WorldGenConfig::default() };
+        let context: GenerationContext = test_context(&config, &[&TerrainPass, &LiquidsPass, &SpawnPointPass]);
+
+        let (spawn_x, spawn_y) = context.world.spawn_point;
+        assert_eq!(spawn_x, config.map_width as i32 / 2);
+        assert_eq!(spawn_y, context.surface_height_map[spawn_x as usize] as i32);
+        assert_eq!(context.world.get_block(spawn_x, spawn_y - 1), Some(context.palette.water), "the middle column is under water");
+    }
+}
@@ -0,0 +1,70 @@
+use rand::Rng;
+use crate::world::world_generator::{GenerationContext, GenerationPass};
+use crate::world::world_generator::passes::{set_block, set_wall};
+
+// buries small lit wooden cabins underground
+pub struct StructuresPass;
+
+impl StructuresPass {
+    const CABIN_WIDTH_RANGE: std::ops::RangeInclusive<u32> = 9..=13;
+    // including the floor and the ceiling
+    const CABIN_HEIGHT: u32 = 6;
+}
+
+impl GenerationPass for StructuresPass {
+    fn name(&self) -> &'static str {
+        "Structures"
+    }
+
+    fn run(&self, context: &mut GenerationContext, report_progress: &mut dyn FnMut(f32)) {
+        let config = context.config;
+
+        let cabin_count: u32 = (config.map_width as f32 * config.cabin_density) as u32;
+        for cabin_index in 0..cabin_count {
+            report_progress(cabin_index as f32 / cabin_count as f32);
+
+            let cabin_width: u32 = context.rng.gen_range(Self::CABIN_WIDTH_RANGE);
+            if cabin_width >= config.map_width {
+                continue;
+            }
+
+            let left_x: u32 = context.rng.gen_range(0..config.map_width - cabin_width);
+            let floor_y: u32 = context.surface_height_map[left_x as usize] + context.rng.gen_range(config.cabin_depth_range.clone());
+            if floor_y >= config.map_height {
+                continue;
+            }
+            // a shallow depth range can put the ceiling above the top of the map
+            let Some(top_y) = (floor_y + 1).checked_sub(Self::CABIN_HEIGHT) else {
+                continue;
+            };
+
+            for x in left_x..left_x + cabin_width {
+                for y in top_y..=floor_y {
+                    let is_shell: bool = x == left_x || x == left_x + cabin_width - 1 || y == top_y || y == floor_y;
+
+                    set_block(&mut context.world, x, y, if is_shell { context.palette.planks } else { context.palette.air });
+                    set_wall(&mut context.world, x, y, context.palette.wood_wall);
+                }
+            }
+
+            set_block(&mut context.world, left_x + cabin_width / 2, top_y + 1, context.palette.torch);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::world_generator::WorldGenConfig;
+    use crate::world::world_generator::passes::test_context;
+
+    #[test]
+    fn cabins_that_would_reach_above_the_map_are_skipped() {
+        let config: WorldGenConfig = WorldGenConfig { map_width: 200, map_height: 250, cabin_density: 1.0 / 10.0, cabin_depth_range: 0..=2, ..WorldGenConfig::default() };
+
+        // without a terrain pass every surface is at the top of the map, so no cabin fits
+        let context: GenerationContext = test_context(&config, &[&StructuresPass]);
+
+        assert_eq!(context.world.loaded_chunk_count(), 0);
+    }
+}
@@ -0,0 +1,66 @@
+use crate::world::world_generator::{BiomePalette, GenerationContext, GenerationPass, SURFACE_NOISE_SALT};
+use crate::world::world_generator::biome::{Biome, BiomeDefinition};
+use crate::world::world_generator::noise::Noise;
+use crate::world::world_generator::passes::{set_block, set_wall};
+
+// shapes the surface and fills every column with the blocks and walls of its biome
+pub struct TerrainPass;
+
+impl GenerationPass for TerrainPass {
+    fn name(&self) -> &'static str {
+        "Terrain"
+    }
+
+    fn run(&self, context: &mut GenerationContext, report_progress: &mut dyn FnMut(f32)) {
+        let config = context.config;
+        let (map_width, map_height) = (config.map_width, config.map_height);
+
+        let surface_noise: Noise = Noise::new(config.seed, SURFACE_NOISE_SALT, config.surface_noise.clone());
+
+        // surface height before the biomes reshape it
+        let mut base_height_map: Vec<f32> = vec![0.0; map_width as usize];
+
+        for x in 0..map_width {
+            let (lowest_y, highest_y) = (*config.surface_height_range.start() as f32, *config.surface_height_range.end() as f32);
+
+            base_height_map[x as usize] = lowest_y + (surface_noise.sample_1d(x as f32) + 1.0) / 2.0 * (highest_y - lowest_y);
+        }
+
+        // the biomes reshape the surface around its mean height, which is also the sea level
+        let sea_level: f32 = base_height_map.iter().sum::<f32>() / map_width as f32;
+        context.sea_level = sea_level.round() as u32;
+
+        for x in 0..map_width {
+            // biome height parameters are blended by the biome weights
+            let (mut height_offset, mut height_variation) = (0.0, 0.0);
+            for (biome, weight) in context.biome_map.weights(x) {
+                let definition: BiomeDefinition = biome.definition();
+                height_offset += definition.height_offset * weight;
+                height_variation += definition.height_variation * weight;
+            }
+
+            let shaped_y: f32 = sea_level + (base_height_map[x as usize] - sea_level) * height_variation + height_offset;
+            let surface_y: u32 = (shaped_y.round().max(0.0) as u32).min(map_height - 1 - config.dirt_depth);
+
+            context.surface_height_map[x as usize] = surface_y;
+
+            // columns near a border take their blocks from a random neighbouring biome so the border is not a straight line
+            let biome: Biome = context.biome_map.sample(x, &mut context.rng);
+            let biome_palette: &BiomePalette = context.palette.biome(biome);
+
+            set_block(&mut context.world, x, surface_y, biome_palette.surface);
+
+            // walls start below the surface block so the surface stays open to the sky
+            for y in surface_y + 1..surface_y + 1 + config.dirt_depth {
+                set_block(&mut context.world, x, y, biome_palette.subsurface);
+                set_wall(&mut context.world, x, y, context.palette.dirt_wall);
+            }
+            for y in surface_y + 1 + config.dirt_depth..map_height {
+                set_block(&mut context.world, x, y, biome_palette.underground);
+                set_wall(&mut context.world, x, y, context.palette.stone_wall);
+            }
+
+            report_progress(x as f32 / map_width as f32);
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use std::ops::RangeInclusive;
+use rand::Rng;
+use crate::world::World;
+use crate::world::world_generator::{BiomePalette, GenerationContext, GenerationPass, Palette};
+use crate::world::world_generator::biome::{Biome, Vegetation};
+use crate::world::world_generator::passes::{get_ground_y_if_clear, set_block};
+
+// grows the trees and cacti of every biome on its surface
+pub struct VegetationPass;
+
+impl GenerationPass for VegetationPass {
+    fn name(&self) -> &'static str {
+        "Vegetation"
+    }
+
+    // every column gets an attempt with the chance of its biome
+    fn run(&self, context: &mut GenerationContext, report_progress: &mut dyn FnMut(f32)) {
+        let map_width: u32 = context.config.map_width;
+
+        for x in 0..map_width {
+            let biome: Biome = context.biome_map.dominant(x);
+            let biome_palette: &BiomePalette = context.palette.biome(biome);
+
+            match context.config.vegetation(biome) {
+                Vegetation::None => {},
+                Vegetation::Trees { density, height_range, canopy_size } => {
+                    if context.rng.gen_bool(*density as f64) {
+                        attempt_generate_surface_tree(&mut context.world, &context.palette, biome_palette, x, height_range.clone(), *canopy_size, &mut context.rng);
+                    }
+                },
+                Vegetation::Cacti { density, height_range } => {
+                    if context.rng.gen_bool(*density as f64) {
+                        attempt_generate_cactus(&mut context.world, &context.palette, biome_palette, x, height_range.clone(), &mut context.rng);
+                    }
+                },
+            }
+
+            report_progress(x as f32 / map_width as f32);
+        }
+    }
+}
+
+fn attempt_generate_surface_tree(
+    world: &mut World,
+    palette: &Palette,
+    biome_palette: &BiomePalette,
+    tree_x: u32,
+    tree_height_range: RangeInclusive<u32>,
+    canopy_size: u32,
+    rng: &mut rand_chacha::ChaCha8Rng,
+) {
+    if let Some(tree_base_y) = get_ground_y_if_clear(world, palette, biome_palette.surface, tree_x) {
+        let tree_height: u32 = rng.gen_range(tree_height_range);
+
+        // the canopy must fit inside the map
+        if tree_x < canopy_size || tree_x + canopy_size >= world.width || tree_base_y < tree_height + canopy_size {
+            return;
+        }
+
+        set_block(world, tree_x, tree_base_y, biome_palette.subsurface);
+
+        for tree_segment_y in 0..tree_height {
+            set_block(world, tree_x, tree_base_y - 1 - tree_segment_y, palette.log);
+        }
+
+        for leaf_y in 0..canopy_size {
+            for i in 0..leaf_y * 2 + 1 {
+                set_block(world, tree_x + i - leaf_y, tree_base_y - canopy_size - tree_height + leaf_y, palette.leaves);
+            }
+        }
+    }
+}
+
+fn attempt_generate_cactus(
+    world: &mut World,
+    palette: &Palette,
+    biome_palette: &BiomePalette,
+    cactus_x: u32,
+    cactus_height_range: RangeInclusive<u32>,
+    rng: &mut rand_chacha::ChaCha8Rng,
+) {
+    if let Some(cactus_base_y) = get_ground_y_if_clear(world, palette, biome_palette.surface, cactus_x) {
+        let cactus_height: u32 = rng.gen_range(cactus_height_range).min(cactus_base_y);
+
+        for cactus_segment_y in 0..cactus_height {
+            set_block(world, cactus_x, cactus_base_y - 1 - cactus_segment_y, palette.cactus);
+        }
+    }
+}